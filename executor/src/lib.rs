@@ -5,6 +5,7 @@
 #![feature(cfg_version)]
 
 use self::statistic::Statistic;
+use self::sync::loom_const_fn;
 use core::future::Future;
 use core::marker::PhantomData;
 use varuemb_lockfree::luqueue::{Item, LUQueue};
@@ -13,6 +14,7 @@ pub use proc::*;
 
 pub mod spawner;
 pub mod statistic;
+mod sync;
 pub mod task;
 
 pub trait TaskName: Sized + 'static {
@@ -60,8 +62,10 @@ pub struct Inner {
 }
 
 impl Inner {
-    pub const fn new(notify: fn(&'static Self)) -> Self {
-        Self { notify, list: LUQueue::new(), queue: LUQueue::new() }
+    loom_const_fn! {
+        pub const fn new(notify: fn(&'static Self)) -> Self {
+            Self { notify, list: LUQueue::new(), queue: LUQueue::new() }
+        }
     }
 
     #[inline]
@@ -70,10 +74,16 @@ impl Inner {
     }
 
     pub fn poll(&'static self) {
+        // Only the round pops from the queue, so the taken batch is exactly what was queued when it began
         let mut taker = self.queue.take();
         while let Some(task) = taker.next() {
             unsafe { task.poll() };
         }
+
+        // Tasks woken during the round are left for the next one
+        if !self.queue.is_empty() {
+            self.notify();
+        }
     }
 
     #[inline]
@@ -84,7 +94,7 @@ impl Inner {
     #[inline]
     unsafe fn start_task(&'static self, task: task::Ref) {
         if self.list.push_back(task.0).is_some() {
-            task.0.wake()
+            task.wake()
         }
     }
 
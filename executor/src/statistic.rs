@@ -1,7 +1,52 @@
 use super::task::Ref;
+use super::sync::loom_const_fn;
 use super::Inner as Executor;
 use core::fmt;
-use varuemb_lockfree::luqueue::{Item, LUQueue};
+use varuemb_lockfree::luqueue::{Item, LUQueue, Snapshot};
+
+/// Most tasks of a thread or threads listed at once by `list`, use `snapshot` to pick another capacity.
+/// The list is kept on the stack, so it costs a pointer per entry there
+pub const LIST_CAPACITY: usize = 32;
+
+/// Tasks or threads at one point in time, at most `M`
+pub struct List<T, const M: usize = LIST_CAPACITY> {
+    items: core::iter::Flatten<core::array::IntoIter<Option<T>, M>>,
+    total: usize,
+    consistent: bool,
+}
+impl<T, const M: usize> List<T, M> {
+    fn new<N>(snapshot: Snapshot<N, M>, map: impl Fn(&'static Item<N>) -> T) -> Self {
+        let (total, consistent) = (snapshot.total(), snapshot.is_consistent());
+        let mut items = snapshot.into_iter();
+        Self { items: core::array::from_fn(|_| items.next().map(&map)).into_iter().flatten(), total, consistent }
+    }
+
+    /// Number listed, including those beyond `M`
+    #[inline]
+    pub fn total(&self) -> usize {
+        self.total
+    }
+
+    /// Unset if some did not fit into `M`
+    #[inline]
+    pub fn is_complete(&self) -> bool {
+        self.total <= M
+    }
+
+    /// Unset if the list kept changing while listed, then some may be missed
+    #[inline]
+    pub fn is_consistent(&self) -> bool {
+        self.consistent
+    }
+}
+impl<T, const M: usize> Iterator for List<T, M> {
+    type Item = T;
+
+    #[inline]
+    fn next(&mut self) -> Option<T> {
+        self.items.next()
+    }
+}
 
 pub struct Task(Ref);
 impl fmt::Debug for Task {
@@ -23,7 +68,14 @@ pub struct Thread {
 impl fmt::Display for Thread {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         write!(f, "Thread {}: ", self.name)?;
-        f.debug_list().entries(self.list()).finish()
+        let tasks = self.list();
+        let complete = tasks.is_complete() && tasks.is_consistent();
+        let mut list = f.debug_list();
+        list.entries(tasks);
+        if !complete {
+            list.entry(&format_args!(".."));
+        }
+        list.finish()
     }
 }
 impl fmt::Debug for Thread {
@@ -44,9 +96,16 @@ impl Thread {
         self.name
     }
 
+    /// Tasks running at one point in time, at most [`LIST_CAPACITY`]
+    #[inline]
+    pub fn list(&self) -> List<Task> {
+        self.snapshot()
+    }
+
+    /// Tasks running at one point in time, at most `M`
     #[inline]
-    pub fn list(&self) -> impl Iterator<Item = Task> {
-        self.executor.list.into_iter().map(|task| Task(Ref(task)))
+    pub fn snapshot<const M: usize>(&self) -> List<Task, M> {
+        List::new(self.executor.list.snapshot(), |task| Task(Ref(task)))
     }
 }
 
@@ -54,9 +113,11 @@ pub struct Statistic {
     threads: LUQueue<Thread>,
 }
 impl Statistic {
-    #[inline]
-    pub const fn new() -> Self {
-        Self { threads: LUQueue::new() }
+    loom_const_fn! {
+        #[inline]
+        pub const fn new() -> Self {
+            Self { threads: LUQueue::new() }
+        }
     }
 
     #[inline]
@@ -69,14 +130,111 @@ impl Statistic {
         self.threads.pop(thread)
     }
 
+    /// Threads running at one point in time, at most [`LIST_CAPACITY`]
+    #[inline]
+    pub fn list(&'static self) -> List<&'static Thread> {
+        self.snapshot()
+    }
+
+    /// Threads running at one point in time, at most `M`
     #[inline]
-    pub fn list(&'static self) -> impl Iterator<Item = &'static Thread> {
-        self.threads.into_iter().map(|task| &**task)
+    pub fn snapshot<const M: usize>(&'static self) -> List<&'static Thread, M> {
+        List::new(self.threads.snapshot(), |thread| &**thread)
     }
 
+    /// Walks all the threads, not only the listed ones
     #[inline]
     #[allow(unused)]
     pub fn get(&'static self, name: &'static str) -> Option<&'static Thread> {
-        self.list().find(|thread| thread.name == name)
+        (&self.threads).into_iter().map(|thread| &**thread).find(|thread| thread.name == name)
+    }
+}
+
+#[cfg(all(test, loom))]
+mod tests {
+    use super::{Executor, Statistic, Thread};
+    use crate::task::{Ref, Task};
+    use loom::thread;
+    use varuemb_lockfree::luqueue::Item;
+
+    fn task() -> Ref {
+        Ref(Box::leak(Box::new(Item::new(Item::new(Task::new())))))
+    }
+
+    fn named(name: &'static str, executor: &'static Executor) -> &'static Item<Thread> {
+        Box::leak(Box::new(Item::new(Thread { name, executor })))
+    }
+
+    #[test]
+    fn list_while_tasks_churn() {
+        loom::model(|| {
+            let executor: &'static Executor = Box::leak(Box::new(Executor::new(|_| {})));
+            let thread = Thread { name: "main", executor };
+            let (first, churning, last) = (task(), task(), task());
+            for task in [&first, &churning, &last] {
+                executor.list.push_back(task.0);
+            }
+
+            let th = thread::spawn(move || {
+                executor.stop_task(churning);
+                executor.list.push_back(churning.0);
+            });
+            let list = thread.list();
+            let consistent = list.is_consistent();
+            let listed: Vec<_> = list.map(|task| task.0 .0 as *const _).collect();
+            th.join().unwrap();
+
+            assert!(listed.contains(&(first.0 as *const _)));
+            if !consistent {
+                return;
+            }
+            assert!(listed.contains(&(last.0 as *const _)));
+            assert!(matches!(listed.len(), 2 | 3));
+        });
+    }
+
+    #[test]
+    fn list_while_threads_churn() {
+        loom::model(|| {
+            let executor: &'static Executor = Box::leak(Box::new(Executor::new(|_| {})));
+            let statistic: &'static Statistic = Box::leak(Box::new(Statistic::new()));
+            let (first, churning, last) = (named("first", executor), named("churning", executor), named("last", executor));
+            for thread in [first, churning, last] {
+                statistic.new_thread(thread).unwrap();
+            }
+
+            let th = thread::spawn(move || {
+                statistic.delete_thread(churning).unwrap();
+                statistic.new_thread(churning).unwrap();
+            });
+            let list = statistic.list();
+            let consistent = list.is_consistent();
+            let listed: Vec<_> = list.map(|thread| thread.name).collect();
+            th.join().unwrap();
+
+            assert!(listed.contains(&"first"));
+            if !consistent {
+                return;
+            }
+            assert!(listed.contains(&"last"));
+            assert!(matches!(listed.len(), 2 | 3));
+        });
+    }
+
+    #[test]
+    fn list_beyond_capacity() {
+        loom::model(|| {
+            let executor: &'static Executor = Box::leak(Box::new(Executor::new(|_| {})));
+            let statistic: &'static Statistic = Box::leak(Box::new(Statistic::new()));
+            for name in ["first", "second", "third"] {
+                statistic.new_thread(named(name, executor)).unwrap();
+            }
+
+            let list = statistic.snapshot::<2>();
+            assert_eq!(list.total(), 3);
+            assert!(!list.is_complete());
+            assert_eq!(list.map(|thread| thread.name).collect::<Vec<_>>(), ["first", "second"]);
+            assert!(statistic.list().is_complete());
+        });
     }
 }
@@ -1,7 +1,7 @@
 use super::{Inner as Executor, Task as Instance};
+use crate::sync::{loom_const_fn, AtomicPtr};
 use core::cell::SyncUnsafeCell;
 use core::future::Future;
-use core::sync::atomic::AtomicPtr;
 use core::sync::atomic::Ordering::*;
 use core::task::{Context, Poll};
 use core::{fmt, mem, pin, ptr};
@@ -14,8 +14,12 @@ pub(super) mod waker;
 type FmtFn = fn(*const Task, &'static Task, &mut fmt::Formatter<'_>, bool) -> fmt::Result;
 type PollFn = unsafe fn(&'static Task);
 
-const fn null_ptr<T>() -> AtomicPtr<T> {
-    AtomicPtr::new(ptr::null_mut())
+const _: () = assert!(mem::align_of::<Task>() > state::TAG as usize);
+
+loom_const_fn! {
+    const fn null_ptr<T>() -> AtomicPtr<T> {
+        AtomicPtr::new(ptr::null_mut())
+    }
 }
 
 #[repr(transparent)]
@@ -25,6 +29,12 @@ impl Ref {
     pub(super) fn from_task(task: &'static Task) -> Self {
         Self(unsafe { &*(task as *const Task as *const Item<Item<Task>>) })
     }
+
+    /// Wakes the task running in the slot right now
+    pub(super) unsafe fn wake(self) {
+        let task: &'static Task = self.0;
+        task.wake(task.generation(), state::GENERATION_MAX)
+    }
 }
 
 struct VTable {
@@ -38,8 +48,10 @@ pub(super) struct Data {
     vtable: VTable,
 }
 impl Data {
-    const fn new() -> Self {
-        Self { executor: null_ptr(), pool: null_ptr(), vtable: VTable { fmt_fn: null_ptr(), poll_fn: null_ptr() } }
+    loom_const_fn! {
+        const fn new() -> Self {
+            Self { executor: null_ptr(), pool: null_ptr(), vtable: VTable { fmt_fn: null_ptr(), poll_fn: null_ptr() } }
+        }
     }
 
     fn executor(&self, executor: &'static Executor) -> Result<&Self, &'static str> {
@@ -68,14 +80,19 @@ impl Data {
     }
 }
 
+/// Aligned so that the low bits of its address are free for the generation tag of wakers.
+/// Pointer alignment already gives that on 64-bit targets, so only 32-bit ones pay up to 4 bytes of padding
+#[repr(align(8))]
 pub(super) struct Task {
     pub(super) data: Data,
     state: state::State,
     stat: stat::Statistic,
 }
 impl Task {
-    const fn new() -> Self {
-        Self { data: Data::new(), state: state::State::new(), stat: stat::Statistic::new() }
+    loom_const_fn! {
+        pub(super) const fn new() -> Self {
+            Self { data: Data::new(), state: state::State::new(), stat: stat::Statistic::new() }
+        }
     }
 
     pub(super) unsafe fn poll(&'static self) {
@@ -88,11 +105,12 @@ impl Task {
         }
     }
 
-    pub(super) unsafe fn wake(&'static self) {
+    /// Queues the task, unless the `mask` bits of `generation` belong to a task that ran in this slot before
+    pub(super) unsafe fn wake(&'static self, generation: u32, mask: u32) {
         let Some(executor) = ptr::NonNull::new(self.data.executor.load(Acquire)) else {
             return;
         };
-        if self.state.ready() {
+        if self.state.ready(generation, mask) {
             executor.as_ref().enqueue(Ref::from_task(self));
         }
     }
@@ -107,6 +125,11 @@ impl Task {
         self as *const Self
     }
 
+    #[inline]
+    pub(super) fn generation(&self) -> u32 {
+        self.state.generation()
+    }
+
     #[inline]
     pub(super) unsafe fn from_ptr(ptr: *const Self) -> &'static Self {
         ptr.as_ref().unwrap_unchecked()
@@ -141,10 +164,13 @@ struct Storage<T: Instance> {
     future: mem::MaybeUninit<SyncUnsafeCell<T::Fut>>,
 }
 impl<T: Instance> Storage<T> {
+    #[cfg(not(loom))]
     const INIT: Storage<T> = Storage::new();
 
-    const fn new() -> Self {
-        Self { task: Item::new(Item::new(Task::new())), future: mem::MaybeUninit::uninit() }
+    loom_const_fn! {
+        const fn new() -> Self {
+            Self { task: Item::new(Item::new(Task::new())), future: mem::MaybeUninit::uninit() }
+        }
     }
 
     #[inline]
@@ -172,13 +198,11 @@ impl<T: Instance> Storage<T> {
     }
 
     unsafe fn deinit(&'static self) {
-        let is_ready = self.task.state.finish();
+        // The slot is freed by `end` or, if the task was woken meanwhile, by its queued poll
+        self.task.state.finish();
 
         let executor = &*self.task.data.executor.swap(ptr::null_mut(), SeqCst);
         executor.stop_task(Ref(&self.task));
-        if is_ready {
-            self.task.state.despawn();
-        }
 
         self.task.stat.clear();
         self.task.data.vtable.poll_fn.store(ptr::null_mut(), SeqCst);
@@ -220,10 +244,16 @@ impl<T: Instance> PoolRef<'static, T> {
 
 pub struct Pool<T: Instance, const SIZE: usize>([Storage<T>; SIZE]);
 impl<T: Instance, const SIZE: usize> Pool<T, SIZE> {
+    #[cfg(not(loom))]
     pub const fn new() -> Self {
         Self([Storage::INIT; SIZE])
     }
 
+    #[cfg(loom)]
+    pub fn new() -> Self {
+        Self(core::array::from_fn(|_| Storage::new()))
+    }
+
     #[inline(always)]
     pub fn as_ref(&self) -> PoolRef<T> {
         PoolRef(&self.0, Self::fmt)
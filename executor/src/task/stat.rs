@@ -1,7 +1,6 @@
-use core::{
-    fmt,
-    sync::atomic::{AtomicUsize, Ordering::*},
-};
+use crate::sync::{loom_const_fn, AtomicUsize};
+use core::fmt;
+use core::sync::atomic::Ordering::*;
 
 #[derive(Debug)]
 pub struct Statistic {
@@ -13,8 +12,10 @@ impl fmt::Display for Statistic {
     }
 }
 impl Statistic {
-    pub const fn new() -> Self {
-        Self { run_count: AtomicUsize::new(0) }
+    loom_const_fn! {
+        pub const fn new() -> Self {
+            Self { run_count: AtomicUsize::new(0) }
+        }
     }
 
     #[inline]
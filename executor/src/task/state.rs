@@ -1,7 +1,6 @@
-use core::{
-    fmt,
-    sync::atomic::{AtomicU32, Ordering::*},
-};
+use crate::sync::{loom_const_fn, AtomicU32};
+use core::fmt;
+use core::sync::atomic::Ordering::*;
 
 #[allow(unused)]
 #[repr(u32)]
@@ -14,9 +13,17 @@ enum Bits {
 
 const SPAWNED: u32 = 1 << Bits::Spawned as u32;
 const FINISHED: u32 = 1 << Bits::Finished as u32;
-#[allow(unused)]
 const READY: u32 = 1 << Bits::Ready as u32;
 const RUNNING: u32 = 1 << Bits::Running as u32;
+const FLAGS: u32 = SPAWNED | FINISHED | READY | RUNNING;
+
+/// Bits of the generation carried by wakers, the rest doesn't fit next to the task address.
+///
+/// A waker kept across a multiple of 8 reuses of its slot matches again and wakes the task running there,
+/// which only costs that task a spurious poll
+pub const TAG: u32 = 0b111;
+/// All the bits of the generation, compared when the wake comes from the slot itself
+pub const GENERATION_MAX: u32 = (1 << 24) - 1;
 
 proc_bitfield::bitfield! {
     struct Repr(u32): Debug {
@@ -24,32 +31,48 @@ proc_bitfield::bitfield! {
         finished: bool @ 1,
         ready: bool @ 2,
         running: bool @ 3,
+        /// Times the slot was freed, so a task knows wakes meant for its predecessors
+        generation: u32 @ 8..32,
     }
 }
 pub struct State(AtomicU32);
 impl State {
-    pub const fn new() -> Self {
-        Self(AtomicU32::new(0))
+    loom_const_fn! {
+        pub const fn new() -> Self {
+            Self(AtomicU32::new(0))
+        }
     }
 
     #[inline]
     pub fn spawn(&self) -> bool {
-        self.0.compare_exchange(0, SPAWNED, SeqCst, SeqCst).is_ok()
+        self.update(|this| (this.0 & FLAGS == 0).then(|| this.with_spawned(true))).is_ok()
     }
 
     #[inline]
     pub fn despawn(&self) {
-        self.0.store(0, SeqCst);
+        self.update(|this| Some(Repr(0).with_generation(this.generation().wrapping_add(1) & GENERATION_MAX)))
+            .unwrap();
     }
 
     #[inline]
-    pub fn finish(&self) -> bool {
-        self.0.fetch_or(FINISHED, SeqCst) & READY != 0
+    pub fn generation(&self) -> u32 {
+        Repr(self.0.load(SeqCst)).generation()
     }
 
     #[inline]
-    pub fn ready(&self) -> bool {
-        self.update(|this| (this.spawned() && !this.ready() && !this.finished()).then(|| this.with_ready(true))).is_ok()
+    pub fn finish(&self) {
+        self.0.fetch_or(FINISHED, SeqCst);
+    }
+
+    /// Queues the task unless it is queued or finished already, or the wake is meant for another generation.
+    /// Only the `mask` bits of `generation` are compared, [`TAG`] for wakers
+    #[inline]
+    pub fn ready(&self, generation: u32, mask: u32) -> bool {
+        self.update(|this| {
+            let current = (this.generation() ^ generation) & mask == 0;
+            (current && this.spawned() && !this.ready() && !this.finished()).then(|| this.with_ready(true))
+        })
+        .is_ok()
     }
 
     // #[inline]
@@ -123,3 +146,99 @@ impl fmt::Display for State {
             .finish()
     }
 }
+
+#[cfg(all(test, loom))]
+mod tests {
+    use super::{State, GENERATION_MAX, TAG};
+    use loom::thread;
+
+    fn spawned() -> &'static State {
+        let state = Box::leak(Box::new(State::new()));
+        assert!(state.spawn());
+        state
+    }
+
+    #[test]
+    fn wake_racing_wake() {
+        loom::model(|| {
+            let state = spawned();
+
+            let generation = state.generation();
+            let th = thread::spawn(move || state.ready(generation, GENERATION_MAX));
+            let woken = state.ready(generation, GENERATION_MAX);
+
+            assert!(woken ^ th.join().unwrap());
+        });
+    }
+
+    #[test]
+    fn wake_while_running() {
+        loom::model(|| {
+            let state = spawned();
+            assert!(state.begin());
+
+            let generation = state.generation();
+            let th = thread::spawn(move || state.ready(generation, GENERATION_MAX));
+            state.end();
+
+            if th.join().unwrap() {
+                assert!(state.begin());
+                state.end();
+            }
+            assert!(!state.spawn());
+        });
+    }
+
+    #[test]
+    fn wake_racing_finish() {
+        loom::model(|| {
+            let state = spawned();
+            assert!(state.begin());
+
+            let generation = state.generation();
+            let th = thread::spawn(move || state.ready(generation, GENERATION_MAX));
+            state.finish();
+            state.end();
+
+            if th.join().unwrap() {
+                // The queued poll has to drop the task instead of running it
+                assert!(!state.begin());
+                state.despawn();
+            }
+            assert!(state.spawn());
+        });
+    }
+
+    #[test]
+    fn wake_from_previous_generation() {
+        loom::model(|| {
+            let state = spawned();
+            let stale = state.generation();
+            assert!(state.begin());
+            state.finish();
+            state.end();
+
+            let th = thread::spawn(move || state.ready(stale, TAG));
+            assert!(state.spawn());
+
+            assert!(!th.join().unwrap());
+            assert!(state.ready(state.generation(), GENERATION_MAX));
+        });
+    }
+
+    #[test]
+    fn stale_tag_after_wrap() {
+        loom::model(|| {
+            let state = spawned();
+            let stale = state.generation();
+            for _ in 0..=TAG {
+                state.despawn();
+            }
+            assert!(state.spawn());
+
+            // Only the full generation tells the slot was reused since
+            assert!(!state.ready(stale, GENERATION_MAX));
+            assert!(state.ready(stale, TAG));
+        });
+    }
+}
@@ -1,13 +1,21 @@
+use super::state::TAG;
 use super::Task;
 use core::task::{RawWaker, RawWakerVTable, Waker};
 
 static VTABLE: RawWakerVTable = RawWakerVTable::new(clone, wake, wake, drop);
 
+/// Splits the waker data into the task and the low bits of the generation it was made for
+unsafe fn untag(data: *const ()) -> (&'static Task, u32) {
+    let generation = data as usize & TAG as usize;
+    (Task::from_ptr(data.cast::<u8>().wrapping_sub(generation).cast()), generation as u32)
+}
+
 unsafe fn clone(task: *const ()) -> RawWaker {
     RawWaker::new(task, &VTABLE)
 }
 unsafe fn wake(task: *const ()) {
-    Task::from_ptr(task.cast()).wake()
+    let (task, generation) = untag(task);
+    task.wake(generation, TAG)
 }
 unsafe fn drop(_: *const ()) {
     /*nothing*/
@@ -15,7 +23,14 @@ unsafe fn drop(_: *const ()) {
 
 #[allow(unused)]
 pub unsafe fn make_waker(task: &'static Task) -> Waker {
-    Waker::from_raw(RawWaker::new(task.as_ptr().cast(), &VTABLE))
+    let data = task.as_ptr().cast::<u8>().wrapping_add((task.generation() & TAG) as usize);
+    Waker::from_raw(RawWaker::new(data.cast(), &VTABLE))
+}
+
+/// Returns the task only if the waker was made for the one running in the slot right now
+unsafe fn current(data: *const ()) -> Option<&'static Task> {
+    let (task, generation) = untag(data);
+    ((task.generation() ^ generation) & TAG == 0).then_some(task)
 }
 
 #[allow(unused)]
@@ -27,7 +42,7 @@ pub fn get_task(waker: &Waker) -> Option<&'static Task> {
         return None;
     }
 
-    Some(unsafe { Task::from_ptr(waker.data().cast()) })
+    unsafe { current(waker.data()) }
 }
 
 #[allow(unused)]
@@ -39,5 +54,5 @@ pub fn get_task(waker: &Waker) -> Option<&'static Task> {
         return None;
     }
 
-    Some(unsafe { Task::from_ptr(raw.data().cast()) })
+    unsafe { current(raw.data()) }
 }
@@ -0,0 +1,90 @@
+//! `RingQueue` against `embassy_sync::channel::Channel` behind a critical section.
+//!
+//! Run with `cargo bench -p varuemb-lockfree --bench ring`
+use criterion::{criterion_group, criterion_main, BatchSize, Criterion, Throughput};
+use embassy_sync::blocking_mutex::raw::CriticalSectionRawMutex;
+use embassy_sync::channel::Channel;
+use std::hint::black_box;
+use std::thread;
+use varuemb_lockfree::RingQueue;
+
+const CAPACITY: usize = 64;
+const MESSAGES: u64 = 1_000;
+
+type Ring = RingQueue<u64, CAPACITY>;
+type Embassy = Channel<CriticalSectionRawMutex, u64, CAPACITY>;
+
+/// Moves `MESSAGES` values through every producer/consumer pair, yielding while full or empty
+fn transfer(pairs: u64, push: impl Fn(u64) -> bool + Sync, pop: impl Fn() -> bool + Sync) {
+    thread::scope(|s| {
+        for _ in 0..pairs {
+            s.spawn(|| {
+                for i in 0..MESSAGES {
+                    while !push(black_box(i)) {
+                        thread::yield_now()
+                    }
+                }
+            });
+            s.spawn(|| {
+                for _ in 0..MESSAGES {
+                    while !pop() {
+                        thread::yield_now()
+                    }
+                }
+            });
+        }
+    })
+}
+
+fn single_thread(c: &mut Criterion) {
+    let mut group = c.benchmark_group("single_thread");
+    group.throughput(Throughput::Elements(1));
+
+    let ring = Ring::new();
+    group.bench_function("ring", |b| {
+        b.iter(|| {
+            ring.try_push(black_box(1)).unwrap();
+            black_box(ring.try_pop().unwrap())
+        })
+    });
+
+    let channel = Embassy::new();
+    group.bench_function("embassy", |b| {
+        b.iter(|| {
+            channel.try_send(black_box(1)).unwrap();
+            black_box(channel.try_receive().unwrap())
+        })
+    });
+
+    group.finish();
+}
+
+fn threaded(c: &mut Criterion) {
+    for (name, pairs) in [("spsc", 1), ("mpmc", 2)] {
+        let mut group = c.benchmark_group(name);
+        group.throughput(Throughput::Elements(MESSAGES * pairs));
+
+        group.bench_function("ring", |b| {
+            b.iter_batched_ref(
+                Ring::new,
+                |ring| transfer(pairs, |value| ring.try_push(value).is_ok(), || black_box(ring.try_pop()).is_some()),
+                BatchSize::SmallInput,
+            )
+        });
+
+        group.bench_function("embassy", |b| {
+            b.iter_batched_ref(
+                Embassy::new,
+                |channel| {
+                    transfer(pairs, |value| channel.try_send(value).is_ok(), || black_box(channel.try_receive()).is_ok())
+                },
+                BatchSize::SmallInput,
+            )
+        });
+
+        group.finish();
+    }
+}
+
+criterion_group!(benches, single_thread, threaded);
+criterion_main!(benches);
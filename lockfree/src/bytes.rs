@@ -0,0 +1,369 @@
+use crate::sync::{loom_const_fn, AtomicBool, AtomicUsize};
+use crate::waker::AtomicWaker;
+use core::cell::UnsafeCell;
+use core::ops::{Deref, DerefMut};
+use core::sync::atomic::Ordering::*;
+use core::{fmt, ptr};
+
+/// Single-producer single-consumer byte ring with contiguous grants (bip-buffer).
+///
+/// Every operation finishes in a fixed number of steps, so either half can live in an interrupt handler.
+/// One byte is kept free to tell a full ring from an empty one, so it holds at most `N - 1` bytes
+pub struct ByteRing<const N: usize> {
+    buf: UnsafeCell<[u8; N]>,
+    /// Where the producer writes next. Only the producer moves it
+    write: AtomicUsize,
+    /// Where the consumer reads next. Only the consumer moves it
+    read: AtomicUsize,
+    /// End of the data left behind when the producer wrapped ahead of the consumer
+    last: AtomicUsize,
+    split: AtomicBool,
+    readable: AtomicWaker,
+    writable: AtomicWaker,
+}
+
+unsafe impl<const N: usize> Sync for ByteRing<N> {}
+
+impl<const N: usize> ByteRing<N> {
+    loom_const_fn! {
+        pub const fn new() -> Self {
+            Self {
+                buf: UnsafeCell::new([0; N]),
+                write: AtomicUsize::new(0),
+                read: AtomicUsize::new(0),
+                last: AtomicUsize::new(0),
+                split: AtomicBool::new(false),
+                readable: AtomicWaker::new(),
+                writable: AtomicWaker::new(),
+            }
+        }
+    }
+
+    /// Hands out the two halves. Only the first call gets them
+    pub fn try_split(&self) -> Option<(Producer<'_, N>, Consumer<'_, N>)> {
+        (!self.split.swap(true, AcqRel)).then_some((Producer { ring: self }, Consumer { ring: self }))
+    }
+
+    #[inline]
+    pub const fn capacity(&self) -> usize {
+        N - 1
+    }
+
+    /// Number of bytes waiting to be read. Only a hint while the halves are in use
+    pub fn len(&self) -> usize {
+        let read = self.read.load(Acquire);
+        let write = self.write.load(Acquire);
+        if write < read {
+            self.last.load(Acquire).saturating_sub(read) + write
+        } else {
+            write - read
+        }
+    }
+
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    #[inline]
+    fn slice(&self, start: usize, len: usize) -> *mut [u8] {
+        ptr::slice_from_raw_parts_mut(self.buf.get().cast::<u8>().wrapping_add(start), len)
+    }
+}
+
+impl<const N: usize> Default for ByteRing<N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<const N: usize> fmt::Debug for ByteRing<N> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ByteRing").field("len", &self.len()).field("capacity", &self.capacity()).finish()
+    }
+}
+
+pub struct Producer<'a, const N: usize> {
+    ring: &'a ByteRing<N>,
+}
+
+impl<'a, const N: usize> Producer<'a, N> {
+    /// Free bytes behind the write index, and at the start of the buffer if the producer may wrap there
+    #[inline]
+    fn space(&self) -> (usize, usize, usize) {
+        let write = self.ring.write.load(Relaxed);
+        let read = self.ring.read.load(Acquire);
+        if write < read {
+            (write, read - write - 1, 0)
+        } else {
+            (write, N - write - (read == 0) as usize, read.saturating_sub(1))
+        }
+    }
+
+    /// Grants exactly `len` contiguous bytes, wrapping to the start of the buffer if they don't fit at the end
+    pub fn grant_exact(&mut self, len: usize) -> Option<WriteGrant<'_, N>> {
+        let (write, end, wrap) = self.space();
+        let start = if end >= len {
+            write
+        } else if wrap >= len {
+            0
+        } else {
+            return None;
+        };
+        Some(WriteGrant { ring: self.ring, start, len })
+    }
+
+    /// Grants the largest contiguous run of free bytes, up to `max`
+    pub fn grant_max(&mut self, max: usize) -> Option<WriteGrant<'_, N>> {
+        let (write, end, wrap) = self.space();
+        let (start, len) = if end > 0 {
+            (write, end)
+        } else if wrap > 0 {
+            (0, wrap)
+        } else {
+            return None;
+        };
+        Some(WriteGrant { ring: self.ring, start, len: len.min(max) })
+    }
+
+    /// Copies as much of `data` as fits, returns the number of bytes written
+    pub fn push_slice(&mut self, data: &[u8]) -> usize {
+        let mut written = 0;
+        // The free space is split in two runs at most
+        for _ in 0..2 {
+            let Some(mut grant) = self.grant_max(data.len() - written) else { break };
+            let len = grant.len();
+            grant.copy_from_slice(&data[written..written + len]);
+            grant.commit(len);
+            written += len;
+        }
+        written
+    }
+
+    #[inline]
+    pub fn is_full(&self) -> bool {
+        let (_, end, wrap) = self.space();
+        end == 0 && wrap == 0
+    }
+}
+
+impl<const N: usize> fmt::Debug for Producer<'_, N> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("Producer").field(self.ring).finish()
+    }
+}
+
+/// Bytes granted to the producer. Nothing is written into the ring until they are committed
+pub struct WriteGrant<'p, const N: usize> {
+    ring: &'p ByteRing<N>,
+    start: usize,
+    len: usize,
+}
+
+impl<const N: usize> WriteGrant<'_, N> {
+    /// Makes the first `used` bytes of the grant readable
+    pub fn commit(self, used: usize) {
+        let used = used.min(self.len);
+        if used == 0 {
+            return;
+        }
+
+        let write = self.ring.write.load(Relaxed);
+        if self.start != write {
+            // Wrapped: the consumer reads up to the old write index before following
+            self.ring.last.store(write, Relaxed);
+        }
+        self.ring.write.store(self.start + used, Release);
+        self.ring.readable.wake();
+    }
+}
+
+impl<const N: usize> Deref for WriteGrant<'_, N> {
+    type Target = [u8];
+    fn deref(&self) -> &Self::Target {
+        // SAFETY: the range is granted to the producer until the grant is gone
+        unsafe { &*self.ring.slice(self.start, self.len) }
+    }
+}
+
+impl<const N: usize> DerefMut for WriteGrant<'_, N> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        // SAFETY: the range is granted to the producer until the grant is gone
+        unsafe { &mut *self.ring.slice(self.start, self.len) }
+    }
+}
+
+pub struct Consumer<'a, const N: usize> {
+    ring: &'a ByteRing<N>,
+}
+
+impl<'a, const N: usize> Consumer<'a, N> {
+    /// Grants the contiguous run of bytes at the read index
+    pub fn grant(&mut self) -> Option<ReadGrant<'_, N>> {
+        let read = self.ring.read.load(Relaxed);
+        let write = self.ring.write.load(Acquire);
+        let (start, end) = if write < read {
+            match self.ring.last.load(Relaxed) {
+                last if read < last => (read, last),
+                // The data left at the end is read, so follow the producer to the start
+                _ => (0, write),
+            }
+        } else {
+            (read, write)
+        };
+        (start != end).then_some(ReadGrant { ring: self.ring, start, len: end - start })
+    }
+
+    /// Copies as many bytes as are available into `buf`, returns the number of bytes read
+    pub fn pop_slice(&mut self, buf: &mut [u8]) -> usize {
+        let mut read = 0;
+        // The data is split in two runs at most
+        for _ in 0..2 {
+            let Some(grant) = self.grant() else { break };
+            let len = grant.len().min(buf.len() - read);
+            buf[read..read + len].copy_from_slice(&grant[..len]);
+            grant.release(len);
+            read += len;
+        }
+        read
+    }
+
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.ring.is_empty()
+    }
+}
+
+impl<const N: usize> fmt::Debug for Consumer<'_, N> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("Consumer").field(self.ring).finish()
+    }
+}
+
+/// Bytes granted to the consumer. They stay in the ring until they are released
+pub struct ReadGrant<'c, const N: usize> {
+    ring: &'c ByteRing<N>,
+    start: usize,
+    len: usize,
+}
+
+impl<const N: usize> ReadGrant<'_, N> {
+    /// Frees the first `used` bytes of the grant
+    pub fn release(self, used: usize) {
+        let used = used.min(self.len);
+        if used == 0 {
+            return;
+        }
+
+        self.ring.read.store(self.start + used, Release);
+        self.ring.writable.wake();
+    }
+}
+
+impl<const N: usize> Deref for ReadGrant<'_, N> {
+    type Target = [u8];
+    fn deref(&self) -> &Self::Target {
+        // SAFETY: the range is granted to the consumer until the grant is gone
+        unsafe { &*self.ring.slice(self.start, self.len) }
+    }
+}
+
+#[cfg(feature = "io")]
+mod io {
+    use super::{Consumer, Producer};
+    use core::convert::Infallible;
+    use core::future::poll_fn;
+    use core::task::Poll;
+
+    impl<const N: usize> embedded_io::ErrorType for Producer<'_, N> {
+        type Error = Infallible;
+    }
+
+    impl<const N: usize> embedded_io::ErrorType for Consumer<'_, N> {
+        type Error = Infallible;
+    }
+
+    /// Spins until there is room for at least one byte
+    impl<const N: usize> embedded_io::Write for Producer<'_, N> {
+        fn write(&mut self, buf: &[u8]) -> Result<usize, Self::Error> {
+            loop {
+                match self.push_slice(buf) {
+                    0 if !buf.is_empty() => core::hint::spin_loop(),
+                    written => return Ok(written),
+                }
+            }
+        }
+
+        #[inline]
+        fn flush(&mut self) -> Result<(), Self::Error> {
+            Ok(())
+        }
+    }
+
+    impl<const N: usize> embedded_io::WriteReady for Producer<'_, N> {
+        #[inline]
+        fn write_ready(&mut self) -> Result<bool, Self::Error> {
+            Ok(!self.is_full())
+        }
+    }
+
+    /// Spins until there is at least one byte
+    impl<const N: usize> embedded_io::Read for Consumer<'_, N> {
+        fn read(&mut self, buf: &mut [u8]) -> Result<usize, Self::Error> {
+            loop {
+                match self.pop_slice(buf) {
+                    0 if !buf.is_empty() => core::hint::spin_loop(),
+                    read => return Ok(read),
+                }
+            }
+        }
+    }
+
+    impl<const N: usize> embedded_io::ReadReady for Consumer<'_, N> {
+        #[inline]
+        fn read_ready(&mut self) -> Result<bool, Self::Error> {
+            Ok(!self.is_empty())
+        }
+    }
+
+    impl<const N: usize> embedded_io_async::Write for Producer<'_, N> {
+        async fn write(&mut self, buf: &[u8]) -> Result<usize, Self::Error> {
+            let ring = self.ring;
+            poll_fn(|cx| match self.push_slice(buf) {
+                0 if !buf.is_empty() => {
+                    ring.writable.register(cx.waker());
+                    // Room could have been made before the waker was in place
+                    match self.push_slice(buf) {
+                        0 => Poll::Pending,
+                        written => Poll::Ready(Ok(written)),
+                    }
+                }
+                written => Poll::Ready(Ok(written)),
+            })
+            .await
+        }
+
+        #[inline]
+        async fn flush(&mut self) -> Result<(), Self::Error> {
+            Ok(())
+        }
+    }
+
+    impl<const N: usize> embedded_io_async::Read for Consumer<'_, N> {
+        async fn read(&mut self, buf: &mut [u8]) -> Result<usize, Self::Error> {
+            let ring = self.ring;
+            poll_fn(|cx| match self.pop_slice(buf) {
+                0 if !buf.is_empty() => {
+                    ring.readable.register(cx.waker());
+                    // Bytes could have been committed before the waker was in place
+                    match self.pop_slice(buf) {
+                        0 => Poll::Pending,
+                        read => Poll::Ready(Ok(read)),
+                    }
+                }
+                read => Poll::Ready(Ok(read)),
+            })
+            .await
+        }
+    }
+}
@@ -1,7 +1,25 @@
-#![no_std]
+#![cfg_attr(not(loom), no_std)]
 #![feature(cfg_version)]
 #![feature(strict_provenance)]
 #![cfg_attr(not(version("1.84")), feature(exposed_provenance))]
 
+mod sync;
+
+pub mod bytes;
 pub mod luqueue;
-pub use luqueue::{Item as LUQueueItem, LUQueue};
+pub mod pool;
+pub mod ring;
+pub mod seqlock;
+pub mod sorted;
+pub mod triple;
+pub mod waker;
+pub mod wheel;
+
+pub use bytes::ByteRing;
+pub use luqueue::{Handle as LUQueueHandle, Item as LUQueueItem, LUQueue, Snapshot};
+pub use pool::{Pool, PoolBox};
+pub use ring::{AsyncRingQueue, RingQueue};
+pub use seqlock::SeqLock;
+pub use sorted::SortedList;
+pub use triple::TripleBuffer;
+pub use wheel::{Timer, TimerWheel};
@@ -1,9 +1,39 @@
 use self::State::*;
+use crate::sync::{loom_const_fn, spin_loop, AtomicPtr, AtomicUsize};
 use core::ops::Deref;
 use core::sync::atomic::Ordering::*;
-use core::sync::atomic::{AtomicBool, AtomicPtr, AtomicUsize};
 use core::{fmt, ptr};
 
+/// Low bits of a link, always free because items are aligned.
+/// They tell whether the item owning the link is still in the queue
+const TAG: usize = 0b11;
+/// The item is in the queue
+const LIVE: usize = 0b00;
+/// The item is being removed by the one who set the tag. Only the owner may unlink it
+const OWNED: usize = 0b10;
+/// The item is being removed and was pushed back meanwhile, so its owner has to requeue it
+const REPUSH: usize = 0b01;
+/// The item is removed, but is still linked because its owner couldn't unlink it.
+/// Anyone who meets it behind a live link may take it over
+const ABANDONED: usize = 0b11;
+
+const _: () = assert!(core::mem::align_of::<AtomicPtr<()>>() > TAG);
+
+#[inline(always)]
+fn tag<N>(ptr: *mut Item<N>) -> usize {
+    ptr.addr() & TAG
+}
+
+#[inline(always)]
+fn with_tag<N>(ptr: *mut Item<N>, tag: usize) -> *mut Item<N> {
+    ptr.map_addr(|addr| addr & !TAG | tag)
+}
+
+#[inline(always)]
+fn untag<N>(ptr: *mut Item<N>) -> *mut Item<N> {
+    with_tag(ptr, LIVE)
+}
+
 enum State<N: 'static> {
     Empty,
     Last,
@@ -18,7 +48,7 @@ impl<N: 'static> State<N> {
     const fn to_ptr(&self) -> *mut Item<N> {
         match self {
             State::Empty => ptr::null_mut(),
-            State::Last => ptr::without_provenance_mut(1),
+            State::Last => ptr::without_provenance_mut(TAG + 1),
             State::Next(item) => item.to_ptr(),
         }
     }
@@ -47,16 +77,27 @@ impl<N: 'static> State<N> {
 pub struct Item<N> {
     value: N,
     state: AtomicPtr<Item<N>>,
+    /// Times the item left a queue
+    generation: AtomicUsize,
 }
 impl<N: 'static> Item<N> {
-    pub const fn new(value: N) -> Self {
-        Self { value, state: AtomicPtr::new(State::new().to_ptr()) }
+    loom_const_fn! {
+        pub const fn new(value: N) -> Self {
+            Self { value, state: AtomicPtr::new(State::new().to_ptr()), generation: AtomicUsize::new(0) }
+        }
+    }
+
+    /// Changes every time the item is removed, so two visits of the queue with the same generation
+    /// see the item without leaving it in between
+    #[inline]
+    pub fn generation(&self) -> usize {
+        self.generation.load(SeqCst)
     }
 
+    /// Refers to the item while it stays in the queue it is in now
     #[inline]
-    fn from_ptr(ptr: *mut Self) -> Option<&'static Self> {
-        let ptr = ptr::NonNull::new(ptr)?;
-        Some(unsafe { ptr.as_ref() })
+    pub fn handle(&'static self) -> Handle<N> {
+        Handle { item: self, generation: self.generation() }
     }
 
     #[inline]
@@ -89,19 +130,56 @@ impl<N: fmt::Display> fmt::Display for Item<N> {
     }
 }
 
-pub struct Taker<N: 'static> {
-    count: &'static AtomicUsize,
-    current: *mut Item<N>,
+/// An [`Item`] at one of its generations. Once the item leaves the queue, the handle refers to nothing
+pub struct Handle<N: 'static> {
+    item: &'static Item<N>,
+    generation: usize,
 }
-impl<N: 'static> Taker<N> {
-    pub fn next(&mut self) -> Option<&'static Item<N>> {
-        let current = State::from_ptr(self.current).as_item()?;
+impl<N: 'static> Handle<N> {
+    /// The item, if it hasn't been removed since the handle was made
+    #[inline]
+    pub fn get(&self) -> Option<&'static Item<N>> {
+        self.is_current(self.item).then_some(self.item)
+    }
 
-        self.current = current.state.swap(Empty.to_ptr(), SeqCst);
+    #[inline]
+    pub fn generation(&self) -> usize {
+        self.generation
+    }
 
-        self.count.fetch_sub(1, SeqCst);
+    #[inline]
+    fn is_current(&self, item: &'static Item<N>) -> bool {
+        ptr::eq(item, self.item) && item.generation() == self.generation
+    }
+}
+impl<N: 'static> Clone for Handle<N> {
+    #[inline]
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+impl<N: 'static> Copy for Handle<N> {}
+impl<N: 'static> PartialEq for Handle<N> {
+    #[inline]
+    fn eq(&self, other: &Self) -> bool {
+        ptr::eq(self.item, other.item) && self.generation == other.generation
+    }
+}
+impl<N: fmt::Debug + 'static> fmt::Debug for Handle<N> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Handle").field("item", self.item).field("generation", &self.generation).finish()
+    }
+}
 
-        Some(current)
+/// Items taken by [`LUQueue::take`], dropping it takes the rest
+pub struct Taker<N: 'static> {
+    queue: &'static LUQueue<N>,
+    remaining: usize,
+}
+impl<N: 'static> Taker<N> {
+    pub fn next(&mut self) -> Option<&'static Item<N>> {
+        self.remaining = self.remaining.checked_sub(1)?;
+        self.queue.pop_front()
     }
 }
 impl<N: 'static> Drop for Taker<N> {
@@ -110,61 +188,120 @@ impl<N: 'static> Drop for Taker<N> {
     }
 }
 
-enum Push<N: 'static> {
-    Start,
-    TryInsert,
-    Registrate(&'static Item<N>),
+/// Marks a change of the queue in progress until dropped
+struct Change<'a, N: 'static>(&'a LUQueue<N>);
+impl<N: 'static> Drop for Change<'_, N> {
+    #[inline]
+    fn drop(&mut self) {
+        self.0.ended.fetch_add(1, SeqCst);
+    }
 }
 
+/// Walks made by [`LUQueue::snapshot`] before it gives up on a consistent view
+pub const SNAPSHOT_ATTEMPTS: usize = 16;
+
 pub struct LUQueue<N: 'static> {
     count: AtomicUsize,
     head: AtomicPtr<Item<N>>,
-    pop_lock: AtomicBool,
+    /// Changes started and finished. A walk that no change overlapped saw the queue as it was
+    begun: AtomicUsize,
+    ended: AtomicUsize,
 }
 impl<N: 'static> LUQueue<N> {
-    pub const fn new() -> Self {
-        Self { count: AtomicUsize::new(0), head: AtomicPtr::new(ptr::null_mut()), pop_lock: AtomicBool::new(false) }
+    loom_const_fn! {
+        pub const fn new() -> Self {
+            Self {
+                count: AtomicUsize::new(0),
+                head: AtomicPtr::new(ptr::null_mut()),
+                begun: AtomicUsize::new(0),
+                ended: AtomicUsize::new(0),
+            }
+        }
     }
 
     pub fn count(&self) -> usize {
         self.count.load(Relaxed)
     }
 
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.search(|_| true).is_none()
+    }
+
+    /// Collects the items as they are at one point in time, walking again while the queue changes.
+    /// At most `M` items are kept, [`Snapshot::is_complete`] tells if there were more.
+    ///
+    /// Never waits for a change to finish, as it may be made by the context it preempted. After
+    /// [`SNAPSHOT_ATTEMPTS`] walks overlapped by changes the last one is returned,
+    /// with [`Snapshot::is_consistent`] unset
+    pub fn snapshot<const M: usize>(&self) -> Snapshot<N, M> {
+        for _ in 1..SNAPSHOT_ATTEMPTS {
+            if let Some(snapshot) = self.try_snapshot() {
+                return snapshot;
+            }
+            spin_loop();
+        }
+        let ended = self.ended.load(SeqCst);
+        let consistent = self.begun.load(SeqCst) == ended;
+        let mut snapshot = self.walk();
+        snapshot.consistent = consistent && self.begun.load(SeqCst) == ended;
+        snapshot
+    }
+
+    /// Same as [`LUQueue::snapshot`], but gives up if the queue changes during the walk
+    pub fn try_snapshot<const M: usize>(&self) -> Option<Snapshot<N, M>> {
+        let ended = self.ended.load(SeqCst);
+        if self.begun.load(SeqCst) != ended {
+            return None;
+        }
+
+        let snapshot = self.walk();
+        (self.begun.load(SeqCst) == ended).then_some(snapshot)
+    }
+
+    fn walk<const M: usize>(&self) -> Snapshot<N, M> {
+        let mut snapshot = Snapshot { items: [None; M], len: 0, total: 0, consistent: true };
+        for item in self {
+            if let Some(slot) = snapshot.items.get_mut(snapshot.len) {
+                *slot = Some(item);
+                snapshot.len += 1;
+            }
+            snapshot.total += 1;
+        }
+        snapshot
+    }
+
+    /// Takes at most as many items as the queue holds right now,
+    /// so items pushed back while taking are left for the next `take`.
+    ///
+    /// The items are not detached at once but popped from the front one by one,
+    /// so they stay in the queue until [`Taker::next`] reaches them. The batch matches the queue at the call
+    /// only while nobody else pops from it, items popped by someone else meanwhile leave their place
+    /// in the batch to items pushed after the call
     pub fn take(&'static self) -> Taker<N> {
-        Taker { count: &self.count, current: self.head.swap(ptr::null_mut(), SeqCst) }
+        Taker { queue: self, remaining: self.count.load(SeqCst) }
     }
 
     pub fn push_back(&self, node: &'static Item<N>) -> Option<bool> {
-        let mut push = Push::Start;
-
-        let result = loop {
-            push = match push {
-                Push::Start => match node.state.compare_exchange(Empty.to_ptr(), Last.to_ptr(), SeqCst, SeqCst) {
-                    Err(_) => break None,
-                    Ok(_) => Push::TryInsert,
-                },
-                Push::TryInsert => match self.head.compare_exchange(ptr::null_mut(), node.to_ptr(), SeqCst, SeqCst) {
-                    Ok(_) => break Some(true),
-                    Err(ptr) => Push::Registrate(Item::from_ptr(ptr).unwrap()),
-                },
-                Push::Registrate(item) => {
-                    match item.state.compare_exchange_weak(Last.to_ptr(), node.to_ptr(), SeqCst, SeqCst) {
-                        Ok(_) => break Some(false),
-                        Err(ptr) => match State::from_ptr(ptr) {
-                            Empty => Push::TryInsert,
-                            Next(item) => Push::Registrate(item),
-                            Last => Push::Registrate(item),
-                        },
-                    }
+        let _change = self.change();
+        loop {
+            let state = node.state.load(SeqCst);
+            let pushed = match tag(state) {
+                _ if state.is_null() => {
+                    node.state.compare_exchange(state, Last.to_ptr(), SeqCst, SeqCst).map(|_| self.append(node))
                 }
-            }
-        };
+                // The owner requeues it once it's unlinked
+                OWNED => node.state.compare_exchange(state, with_tag(state, REPUSH), SeqCst, SeqCst).map(|_| true),
+                // Still linked, so it's enough to bring it back in place
+                ABANDONED => node.state.compare_exchange(state, untag(state), SeqCst, SeqCst).map(|_| self.is_first(node)),
+                _ => return None,
+            };
 
-        if result.is_some() {
-            self.count.fetch_add(1, SeqCst);
+            if let Ok(is_first) = pushed {
+                self.count.fetch_add(1, SeqCst);
+                return Some(is_first);
+            }
         }
-
-        result
     }
 
     #[inline]
@@ -180,68 +317,274 @@ impl<N: 'static> LUQueue<N> {
         self.pop_impl(|item| item.eq(value))
     }
 
-    fn pop_impl(&'static self, cmp: impl Fn(&'static N) -> bool) -> Option<&'static Item<N>> {
-        self.lock_pop();
+    /// Removes the item the handle refers to. Does nothing if it was removed since the handle was made,
+    /// even if it is back in the queue
+    #[inline]
+    pub fn pop_handle(&'static self, handle: Handle<N>) -> Option<&'static Item<N>> {
+        self.pop_impl(|item| handle.is_current(item))
+    }
 
-        let mut ret = None;
-        let mut current = &self.head;
-        while let Next(item) = State::from_ptr(current.load(SeqCst)) {
-            if !cmp(&item.value) {
-                current = &item.state;
+    pub(crate) fn pop_impl(&self, cmp: impl Fn(&'static Item<N>) -> bool) -> Option<&'static Item<N>> {
+        let _change = self.change();
+        // Logical removal: the item is owned by the one who tags its link
+        let (link, item) = loop {
+            let (link, item) = self.search(&cmp)?;
+            let state = item.state.load(SeqCst);
+            if state.is_null() || tag(state) != LIVE {
+                continue;
+            }
+            if item.state.compare_exchange(state, with_tag(state, OWNED), SeqCst, SeqCst).is_err() {
+                continue;
+            }
+            // It could have been removed and pushed back since the search, which matters to handles
+            if !cmp(item) {
+                self.unclaim(item);
                 continue;
             }
+            break (link, item);
+        };
 
-            let next = {
-                let next = State::from_ptr(item.state.swap(Last.to_ptr(), SeqCst)).as_item().map(|n| n.to_ptr());
-                next.unwrap_or_else(|| self.get_last(current))
-            };
-            current.store(next, SeqCst);
-
-            if let Some(missed) = State::from_ptr(item.state.swap(Empty.to_ptr(), SeqCst)).as_item().map(|i| i.to_ptr()) {
-                loop {
-                    let last = self.get_last(current);
-                    current = match current.compare_exchange(last, missed, SeqCst, SeqCst) {
-                        Ok(_) => break,
-                        Err(ptr) => unsafe { &(*ptr).state },
+        self.count.fetch_sub(1, SeqCst);
+        self.release(link, item);
+
+        Some(item)
+    }
+
+    /// Links `node` in front of the first live item `before` picks, or behind the last one.
+    /// Returns `None` if the item is queued or still being removed by someone else.
+    ///
+    /// Unlike `push_back` it only ever changes live links, so nothing is left behind an item
+    /// being removed and a removed item is never brought back in place, which keeps the order
+    pub(crate) fn insert_by(&self, node: &'static Item<N>, before: impl Fn(&'static N) -> bool) -> Option<bool> {
+        let _change = self.change();
+        loop {
+            let state = node.state.load(SeqCst);
+            match tag(state) {
+                _ if state.is_null() => {
+                    if node.state.compare_exchange(state, Last.to_ptr(), SeqCst, SeqCst).is_ok() {
+                        break;
+                    }
+                }
+                // Unlinking it is all that is left, which is possible if it is reachable through a live link
+                ABANDONED => {
+                    self.search(|_| false);
+                    if node.state.load(SeqCst) == state {
+                        return None;
+                    }
+                }
+                _ => return None,
+            }
+        }
+
+        let is_first = 'retry: loop {
+            // The last live link seen, the node goes there
+            let (mut live, mut value) = (&self.head, self.head.load(SeqCst));
+            let mut link = live;
+            let mut current = value;
+            let mut is_first = true;
+            while let Next(item) = State::from_ptr(untag(current)) {
+                let next = item.state.load(SeqCst);
+                if next.is_null() || link.load(SeqCst) != current || self.adopt(current, link, item, next) {
+                    continue 'retry;
+                }
+                if tag(next) == LIVE {
+                    if before(&item.value) {
+                        break;
                     }
+                    (live, value, is_first) = (&item.state, next, false);
                 }
+                link = &item.state;
+                current = next;
+            }
+            if current.is_null() && !ptr::eq(link, &self.head) {
+                continue 'retry;
             }
 
-            ret = Some(item);
-            break;
+            // Items being removed between the live link and the position are skipped, their owners find the new link
+            node.state.store(if value.is_null() { Last.to_ptr() } else { value }, SeqCst);
+            if live.compare_exchange(value, node.to_ptr(), SeqCst, SeqCst).is_ok() {
+                break is_first;
+            }
+        };
+
+        self.count.fetch_add(1, SeqCst);
+        Some(is_first)
+    }
+
+    /// Links `node` behind the last item. Returns `true` if there is no item in the queue before it
+    fn append(&self, node: &'static Item<N>) -> bool {
+        'retry: loop {
+            let mut link = &self.head;
+            let mut current = link.load(SeqCst);
+            let mut is_first = true;
+            loop {
+                if let Next(item) = State::from_ptr(untag(current)) {
+                    let next = item.state.load(SeqCst);
+                    if next.is_null() || self.adopt(current, link, item, next) {
+                        continue 'retry;
+                    }
+                    is_first &= tag(next) != LIVE;
+                    link = &item.state;
+                    current = next;
+                    continue;
+                }
+                if current.is_null() && !ptr::eq(link, &self.head) {
+                    continue 'retry;
+                }
+
+                // The tag belongs to the item owning the link, so it's kept as is
+                match link.compare_exchange(current, with_tag(node.to_ptr(), tag(current)), SeqCst, SeqCst) {
+                    Ok(_) => break 'retry is_first,
+                    Err(ptr) => current = ptr,
+                }
+            }
         }
+    }
 
-        if ret.is_some() {
-            self.count.fetch_sub(1, SeqCst);
+    /// Unlinks an owned item, or abandons it if its predecessor is being removed too
+    fn release<'a>(&'a self, mut link: &'a AtomicPtr<Item<N>>, item: &'static Item<N>) {
+        loop {
+            let next = untag(item.state.load(SeqCst));
+            let unlinked = match State::from_ptr(next) {
+                Last => self.get_last(link),
+                _ => next,
+            };
+            if link.compare_exchange(item.to_ptr(), unlinked, SeqCst, SeqCst).is_ok() {
+                return self.reset(item, next);
+            }
+
+            match self.link_to(item) {
+                Some(found) => link = found,
+                None => return self.abandon(item),
+            }
         }
-        self.unlock_pop();
+    }
 
-        ret
+    /// Finishes the removal of an unlinked item.
+    /// `next` is the item that followed it at the moment it was unlinked
+    fn reset(&self, item: &'static Item<N>, next: *mut Item<N>) {
+        item.generation.fetch_add(1, SeqCst);
+        let state = loop {
+            let state = item.state.load(SeqCst);
+            let reset = if tag(state) == REPUSH { Last } else { Empty };
+            if item.state.compare_exchange(state, reset.to_ptr(), SeqCst, SeqCst).is_ok() {
+                break state;
+            }
+        };
+
+        // Items pushed behind the last one while it was being unlinked
+        if untag(state) != next {
+            if let Next(missed) = State::from_ptr(untag(state)) {
+                self.append(missed);
+            }
+        }
+        if tag(state) == REPUSH {
+            self.append(item);
+        }
     }
 
-    #[inline(always)]
-    fn get_last(&self, ptr: &'static AtomicPtr<Item<N>>) -> *mut Item<N> {
-        if ptr::from_ref(ptr) == ptr::from_ref(&self.head) {
-            ptr::null_mut()
-        } else {
-            Last.to_ptr()
+    fn abandon(&self, item: &'static Item<N>) {
+        item.generation.fetch_add(1, SeqCst);
+        loop {
+            let state = item.state.load(SeqCst);
+            let abandoned = if tag(state) == REPUSH { untag(state) } else { with_tag(state, ABANDONED) };
+            if item.state.compare_exchange(state, abandoned, SeqCst, SeqCst).is_ok() {
+                return;
+            }
+        }
+    }
+
+    /// Gives back an item claimed by mistake, leaving it in place
+    fn unclaim(&self, item: &'static Item<N>) {
+        loop {
+            let state = item.state.load(SeqCst);
+            if item.state.compare_exchange(state, untag(state), SeqCst, SeqCst).is_ok() {
+                // It was pushed meanwhile and counted twice
+                if tag(state) == REPUSH {
+                    self.count.fetch_sub(1, SeqCst);
+                }
+                return;
+            }
+        }
+    }
+
+    /// Takes over and unlinks an abandoned item reached through the live link `current`.
+    /// Returns `false` if there is nothing to take over
+    fn adopt(&self, current: *mut Item<N>, link: &AtomicPtr<Item<N>>, item: &'static Item<N>, state: *mut Item<N>) -> bool {
+        if tag(current) != LIVE || tag(state) != ABANDONED {
+            return false;
+        }
+        let _change = self.change();
+        if item.state.compare_exchange(state, with_tag(state, OWNED), SeqCst, SeqCst).is_ok() {
+            self.release(link, item);
+        }
+        true
+    }
+
+    /// Returns the live link pointing to `item`
+    fn link_to(&self, item: &'static Item<N>) -> Option<&AtomicPtr<Item<N>>> {
+        'retry: loop {
+            let mut link = &self.head;
+            let mut current = link.load(SeqCst);
+            while let Next(next) = State::from_ptr(untag(current)) {
+                if ptr::eq(next, item) {
+                    return (tag(current) == LIVE).then_some(link);
+                }
+                link = &next.state;
+                current = link.load(SeqCst);
+                if current.is_null() {
+                    continue 'retry;
+                }
+            }
+            return None;
         }
     }
 
     #[inline]
-    fn lock_pop(&self) {
-        while self.pop_lock.swap(true, Acquire) {}
+    fn is_first(&self, item: &'static Item<N>) -> bool {
+        self.search(|_| true).is_some_and(|(_, first)| ptr::eq(first, item))
+    }
+
+    /// Returns the first item in the queue matching `cmp` and the link pointing to it
+    fn search(&self, cmp: impl Fn(&'static Item<N>) -> bool) -> Option<(&AtomicPtr<Item<N>>, &'static Item<N>)> {
+        'retry: loop {
+            let mut link = &self.head;
+            let mut current = link.load(SeqCst);
+            while let Next(item) = State::from_ptr(untag(current)) {
+                let next = item.state.load(SeqCst);
+                if next.is_null() || link.load(SeqCst) != current || self.adopt(current, link, item, next) {
+                    continue 'retry;
+                }
+                if tag(next) == LIVE && cmp(item) {
+                    return Some((link, item));
+                }
+                link = &item.state;
+                current = next;
+            }
+            return None;
+        }
     }
 
     #[inline]
-    fn unlock_pop(&self) {
-        self.pop_lock.store(false, Release)
+    fn change(&self) -> Change<'_, N> {
+        self.begun.fetch_add(1, SeqCst);
+        Change(self)
+    }
+
+    #[inline(always)]
+    fn get_last(&self, ptr: &AtomicPtr<Item<N>>) -> *mut Item<N> {
+        if ptr::from_ref(ptr) == ptr::from_ref(&self.head) {
+            ptr::null_mut()
+        } else {
+            Last.to_ptr()
+        }
     }
 }
 impl<N: 'static> Drop for LUQueue<N> {
     fn drop(&mut self) {
-        for item in &*self {
-            item.state.store(State::Empty.to_ptr(), Release);
+        let mut current = self.head.load(Acquire);
+        while let Next(item) = State::from_ptr(untag(current)) {
+            current = item.state.swap(Empty.to_ptr(), AcqRel);
         }
     }
 }
@@ -255,6 +598,8 @@ impl<'a, N: 'static> IntoIterator for &'a LUQueue<N> {
     }
 }
 
+/// Walks the queue as it is. Items added or removed meanwhile may be missed,
+/// [`LUQueue::snapshot`] gives a consistent view
 pub struct Iter<'a, N> {
     ptr: &'a AtomicPtr<Item<N>>,
 }
@@ -263,8 +608,75 @@ impl<N: 'static> Iterator for Iter<'_, N> {
     type Item = &'static Item<N>;
 
     fn next(&mut self) -> Option<Self::Item> {
-        let item = State::from_ptr(self.ptr.load(Acquire)).as_item()?;
-        self.ptr = &item.state;
-        Some(item)
+        loop {
+            let item = State::from_ptr(untag(self.ptr.load(Acquire))).as_item()?;
+            self.ptr = &item.state;
+            if tag(item.state.load(Acquire)) == LIVE {
+                return Some(item);
+            }
+        }
+    }
+}
+
+/// Items of a queue at one point in time
+pub struct Snapshot<N: 'static, const M: usize> {
+    items: [Option<&'static Item<N>>; M],
+    len: usize,
+    total: usize,
+    consistent: bool,
+}
+
+impl<N: 'static, const M: usize> Snapshot<N, M> {
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Number of items in the queue, including those that didn't fit
+    #[inline]
+    pub fn total(&self) -> usize {
+        self.total
+    }
+
+    #[inline]
+    pub fn is_complete(&self) -> bool {
+        self.len == self.total
+    }
+
+    /// Unset if the queue changed during the walk, then items may be missed
+    #[inline]
+    pub fn is_consistent(&self) -> bool {
+        self.consistent
+    }
+
+    #[inline]
+    pub fn iter(&self) -> impl Iterator<Item = &'static Item<N>> + '_ {
+        self.items[..self.len].iter().flatten().copied()
+    }
+}
+
+impl<N: 'static, const M: usize> IntoIterator for Snapshot<N, M> {
+    type Item = &'static Item<N>;
+    type IntoIter = core::iter::Flatten<core::array::IntoIter<Option<&'static Item<N>>, M>>;
+
+    #[inline]
+    fn into_iter(self) -> Self::IntoIter {
+        self.items.into_iter().flatten()
+    }
+}
+
+impl<N: fmt::Debug + 'static, const M: usize> fmt::Debug for Snapshot<N, M> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut list = f.debug_list();
+        list.entries(self.iter());
+        if !self.is_complete() {
+            list.entry(&format_args!(".."));
+        }
+        list.finish()
     }
 }
@@ -0,0 +1,190 @@
+use crate::sync::{loom_const_fn, AtomicBool, AtomicUsize};
+use core::cell::UnsafeCell;
+use core::mem::{ManuallyDrop, MaybeUninit};
+use core::ops::{Deref, DerefMut};
+use core::sync::atomic::Ordering::*;
+use core::{fmt, ptr};
+
+struct Slot<T> {
+    taken: AtomicBool,
+    value: UnsafeCell<MaybeUninit<T>>,
+}
+impl<T> Slot<T> {
+    loom_const_fn! {
+        const fn new() -> Self {
+            Self { taken: AtomicBool::new(false), value: UnsafeCell::new(MaybeUninit::uninit()) }
+        }
+    }
+}
+
+/// Usage counters of a [`Pool`]
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct Stats {
+    pub capacity: usize,
+    /// Values allocated right now
+    pub used: usize,
+    /// Most values allocated at once
+    pub peak: usize,
+    /// Allocations that failed because the pool was full
+    pub exhausted: usize,
+}
+
+/// Fixed set of `N` slots for values of `T`, handed out as owning [`PoolBox`] handles.
+///
+/// Allocation claims a free slot with a CAS and never blocks, so it is usable from interrupt handlers
+pub struct Pool<T, const N: usize> {
+    /// Where the next allocation starts looking for a free slot
+    hint: AtomicUsize,
+    used: AtomicUsize,
+    peak: AtomicUsize,
+    exhausted: AtomicUsize,
+    slots: [Slot<T>; N],
+}
+
+unsafe impl<T: Send, const N: usize> Send for Pool<T, N> {}
+unsafe impl<T: Send, const N: usize> Sync for Pool<T, N> {}
+
+impl<T, const N: usize> Pool<T, N> {
+    #[cfg(not(loom))]
+    pub const fn new() -> Self {
+        Self {
+            hint: AtomicUsize::new(0),
+            used: AtomicUsize::new(0),
+            peak: AtomicUsize::new(0),
+            exhausted: AtomicUsize::new(0),
+            slots: [const { Slot::new() }; N],
+        }
+    }
+
+    #[cfg(loom)]
+    pub fn new() -> Self {
+        Self {
+            hint: AtomicUsize::new(0),
+            used: AtomicUsize::new(0),
+            peak: AtomicUsize::new(0),
+            exhausted: AtomicUsize::new(0),
+            slots: core::array::from_fn(|_| Slot::new()),
+        }
+    }
+
+    #[inline]
+    pub const fn capacity(&self) -> usize {
+        N
+    }
+
+    /// Moves `value` into a free slot. Returns it back if there is none
+    pub fn alloc(&self, value: T) -> Result<PoolBox<'_, T, N>, T> {
+        let start = self.hint.load(Relaxed);
+        for offset in 0..N {
+            let index = (start + offset) % N;
+            let slot = &self.slots[index];
+            if slot.taken.load(Relaxed) || slot.taken.compare_exchange(false, true, Acquire, Relaxed).is_err() {
+                continue;
+            }
+
+            // SAFETY: the slot is claimed, nobody else touches the value
+            unsafe { (*slot.value.get()).write(value) };
+            self.hint.store((index + 1) % N, Relaxed);
+            let used = self.used.fetch_add(1, Relaxed) + 1;
+            self.peak.fetch_max(used, Relaxed);
+
+            return Ok(PoolBox { pool: self, index });
+        }
+
+        self.exhausted.fetch_add(1, Relaxed);
+        Err(value)
+    }
+
+    pub fn stats(&self) -> Stats {
+        Stats {
+            capacity: N,
+            used: self.used.load(Relaxed),
+            peak: self.peak.load(Relaxed),
+            exhausted: self.exhausted.load(Relaxed),
+        }
+    }
+
+    /// SAFETY: the slot has to hold a value owned by the caller, which is gone afterwards
+    unsafe fn free(&self, index: usize) {
+        self.used.fetch_sub(1, Relaxed);
+        self.slots[index].taken.store(false, Release);
+    }
+
+    #[inline]
+    fn value(&self, index: usize) -> *mut T {
+        self.slots[index].value.get().cast()
+    }
+}
+
+impl<T, const N: usize> Default for Pool<T, N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T, const N: usize> fmt::Debug for Pool<T, N> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("Pool").field(&self.stats()).finish()
+    }
+}
+
+/// Owning handle to a value in a [`Pool`]. The slot is freed when the handle is dropped
+pub struct PoolBox<'p, T, const N: usize> {
+    pool: &'p Pool<T, N>,
+    index: usize,
+}
+
+unsafe impl<T: Send, const N: usize> Send for PoolBox<'_, T, N> {}
+unsafe impl<T: Sync, const N: usize> Sync for PoolBox<'_, T, N> {}
+
+impl<T, const N: usize> PoolBox<'_, T, N> {
+    /// Moves the value out and frees the slot
+    pub fn into_inner(this: Self) -> T {
+        let this = ManuallyDrop::new(this);
+        // SAFETY: the handle owns the value and is not used anymore
+        unsafe {
+            let value = ptr::read(this.pool.value(this.index));
+            this.pool.free(this.index);
+            value
+        }
+    }
+}
+
+impl<T, const N: usize> Deref for PoolBox<'_, T, N> {
+    type Target = T;
+    fn deref(&self) -> &Self::Target {
+        // SAFETY: the handle owns an initialized value
+        unsafe { &*self.pool.value(self.index) }
+    }
+}
+
+impl<T, const N: usize> DerefMut for PoolBox<'_, T, N> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        // SAFETY: the handle owns an initialized value
+        unsafe { &mut *self.pool.value(self.index) }
+    }
+}
+
+impl<T, const N: usize> Drop for PoolBox<'_, T, N> {
+    fn drop(&mut self) {
+        // SAFETY: the handle owns the value and is going away
+        unsafe {
+            ptr::drop_in_place(self.pool.value(self.index));
+            self.pool.free(self.index);
+        }
+    }
+}
+
+impl<T: fmt::Debug, const N: usize> fmt::Debug for PoolBox<'_, T, N> {
+    #[inline]
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(&**self, f)
+    }
+}
+
+impl<T: fmt::Display, const N: usize> fmt::Display for PoolBox<'_, T, N> {
+    #[inline]
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Display::fmt(&**self, f)
+    }
+}
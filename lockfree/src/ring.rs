@@ -0,0 +1,247 @@
+use crate::sync::{loom_const_fn, AtomicUsize};
+use crate::waker::AtomicWaker;
+use core::cell::UnsafeCell;
+use core::fmt;
+use core::future::{poll_fn, Future};
+use core::mem::MaybeUninit;
+use core::sync::atomic::Ordering::*;
+use core::task::{Context, Poll};
+
+/// A slot is free for the push at position `pos` when its sequence equals `pos`,
+/// and holds a value for the pop at `pos` when it equals `pos + 1`
+struct Slot<T> {
+    seq: AtomicUsize,
+    value: UnsafeCell<MaybeUninit<T>>,
+}
+impl<T> Slot<T> {
+    loom_const_fn! {
+        const fn new(seq: usize) -> Self {
+            Self { seq: AtomicUsize::new(seq), value: UnsafeCell::new(MaybeUninit::uninit()) }
+        }
+    }
+}
+
+/// Bounded MPMC queue on a fixed array.
+///
+/// Push and pop never block and never take a critical section, so both ends can be used
+/// from interrupt handlers. `N` has to be a power of two, at least 2
+pub struct RingQueue<T, const N: usize> {
+    head: AtomicUsize,
+    tail: AtomicUsize,
+    slots: [Slot<T>; N],
+}
+
+unsafe impl<T: Send, const N: usize> Send for RingQueue<T, N> {}
+unsafe impl<T: Send, const N: usize> Sync for RingQueue<T, N> {}
+
+impl<T, const N: usize> RingQueue<T, N> {
+    #[cfg(not(loom))]
+    pub const fn new() -> Self {
+        const { assert!(N > 1 && N.is_power_of_two(), "RingQueue capacity must be a power of two above 1") };
+
+        let mut slots = [const { Slot::new(0) }; N];
+        let mut i = 0;
+        while i < N {
+            slots[i] = Slot::new(i);
+            i += 1;
+        }
+        Self { head: AtomicUsize::new(0), tail: AtomicUsize::new(0), slots }
+    }
+
+    #[cfg(loom)]
+    pub fn new() -> Self {
+        const { assert!(N > 1 && N.is_power_of_two(), "RingQueue capacity must be a power of two above 1") };
+
+        Self { head: AtomicUsize::new(0), tail: AtomicUsize::new(0), slots: core::array::from_fn(Slot::new) }
+    }
+
+    #[inline]
+    pub const fn capacity(&self) -> usize {
+        N
+    }
+
+    /// Number of values in the queue. Only a hint while others push or pop
+    pub fn len(&self) -> usize {
+        let tail = self.tail.load(Acquire);
+        let head = self.head.load(Acquire);
+        tail.wrapping_sub(head).min(N)
+    }
+
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    #[inline]
+    pub fn is_full(&self) -> bool {
+        self.len() == N
+    }
+
+    /// Returns the value back if the queue is full
+    pub fn try_push(&self, value: T) -> Result<(), T> {
+        let mut pos = self.tail.load(Relaxed);
+        loop {
+            let slot = &self.slots[pos & (N - 1)];
+            let seq = slot.seq.load(Acquire);
+
+            match (seq.wrapping_sub(pos) as isize).signum() {
+                0 => match self.tail.compare_exchange_weak(pos, pos.wrapping_add(1), Relaxed, Relaxed) {
+                    Ok(_) => {
+                        // SAFETY: the slot is ours until its sequence is bumped
+                        unsafe { (*slot.value.get()).write(value) };
+                        slot.seq.store(pos.wrapping_add(1), Release);
+                        return Ok(());
+                    }
+                    Err(actual) => pos = actual,
+                },
+                // The slot still holds the value from the previous lap
+                -1 => return Err(value),
+                // Another pusher took this position
+                _ => pos = self.tail.load(Relaxed),
+            }
+        }
+    }
+
+    pub fn try_pop(&self) -> Option<T> {
+        let mut pos = self.head.load(Relaxed);
+        loop {
+            let slot = &self.slots[pos & (N - 1)];
+            let seq = slot.seq.load(Acquire);
+
+            match (seq.wrapping_sub(pos.wrapping_add(1)) as isize).signum() {
+                0 => match self.head.compare_exchange_weak(pos, pos.wrapping_add(1), Relaxed, Relaxed) {
+                    Ok(_) => {
+                        // SAFETY: the slot is ours until its sequence is bumped
+                        let value = unsafe { (*slot.value.get()).assume_init_read() };
+                        slot.seq.store(pos.wrapping_add(N), Release);
+                        return Some(value);
+                    }
+                    Err(actual) => pos = actual,
+                },
+                // The slot is not filled yet
+                -1 => return None,
+                // Another popper took this position
+                _ => pos = self.head.load(Relaxed),
+            }
+        }
+    }
+}
+
+impl<T, const N: usize> Drop for RingQueue<T, N> {
+    fn drop(&mut self) {
+        while self.try_pop().is_some() {}
+    }
+}
+
+impl<T, const N: usize> Default for RingQueue<T, N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T, const N: usize> fmt::Debug for RingQueue<T, N> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("RingQueue").field("len", &self.len()).field("capacity", &N).finish()
+    }
+}
+
+/// [`RingQueue`] whose ends can also wait for room or for a value.
+///
+/// Each end wakes only the task that waited on it last, so only one task per end may wait.
+/// Others should stick to `try_push` and `try_pop`
+pub struct AsyncRingQueue<T, const N: usize> {
+    queue: RingQueue<T, N>,
+    pushers: AtomicWaker,
+    poppers: AtomicWaker,
+}
+
+impl<T, const N: usize> AsyncRingQueue<T, N> {
+    loom_const_fn! {
+        pub const fn new() -> Self {
+            Self { queue: RingQueue::new(), pushers: AtomicWaker::new(), poppers: AtomicWaker::new() }
+        }
+    }
+
+    #[inline]
+    pub const fn capacity(&self) -> usize {
+        N
+    }
+
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.queue.len()
+    }
+
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.queue.is_empty()
+    }
+
+    #[inline]
+    pub fn is_full(&self) -> bool {
+        self.queue.is_full()
+    }
+
+    pub fn try_push(&self, value: T) -> Result<(), T> {
+        self.queue.try_push(value)?;
+        self.poppers.wake();
+        Ok(())
+    }
+
+    pub fn try_pop(&self) -> Option<T> {
+        let value = self.queue.try_pop()?;
+        self.pushers.wake();
+        Some(value)
+    }
+
+    /// Waits until there is room for the value
+    pub fn push(&self, value: T) -> impl Future<Output = ()> + '_ {
+        let mut value = Some(value);
+        poll_fn(move |cx| {
+            let Some(pending) = value.take() else { return Poll::Ready(()) };
+            match self.poll_push(cx, pending) {
+                Ok(()) => Poll::Ready(()),
+                Err(pending) => {
+                    value = Some(pending);
+                    Poll::Pending
+                }
+            }
+        })
+    }
+
+    /// Waits until there is a value
+    pub fn pop(&self) -> impl Future<Output = T> + '_ {
+        poll_fn(move |cx| self.poll_pop(cx))
+    }
+
+    pub fn poll_push(&self, cx: &mut Context<'_>, value: T) -> Result<(), T> {
+        let value = match self.try_push(value) {
+            Err(value) => value,
+            pushed => return pushed,
+        };
+        self.pushers.register(cx.waker());
+        // Room could have been made before the waker was in place
+        self.try_push(value)
+    }
+
+    pub fn poll_pop(&self, cx: &mut Context<'_>) -> Poll<T> {
+        if let Some(value) = self.try_pop() {
+            return Poll::Ready(value);
+        }
+        self.poppers.register(cx.waker());
+        // A value could have been pushed before the waker was in place
+        self.try_pop().map_or(Poll::Pending, Poll::Ready)
+    }
+}
+
+impl<T, const N: usize> Default for AsyncRingQueue<T, N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T, const N: usize> fmt::Debug for AsyncRingQueue<T, N> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("AsyncRingQueue").field("len", &self.len()).field("capacity", &N).finish()
+    }
+}
@@ -0,0 +1,84 @@
+use crate::sync::{fence, loom_const_fn, AtomicUsize};
+use core::cell::UnsafeCell;
+use core::mem::MaybeUninit;
+use core::sync::atomic::Ordering::*;
+use core::{fmt, ptr};
+
+/// Sequence lock around a `Copy` value.
+///
+/// The writer never waits for readers. Readers copy the value and retry if a write went on meanwhile,
+/// so they always get a consistent value. A reader that may preempt the writer, e.g. in an interrupt handler,
+/// has to use [`SeqLock::try_read`], otherwise it spins until the preempted write is done, which never happens
+pub struct SeqLock<T: Copy> {
+    /// Odd while a write is in progress
+    seq: AtomicUsize,
+    value: UnsafeCell<T>,
+}
+
+unsafe impl<T: Copy + Send> Send for SeqLock<T> {}
+unsafe impl<T: Copy + Send> Sync for SeqLock<T> {}
+
+impl<T: Copy> SeqLock<T> {
+    loom_const_fn! {
+        pub const fn new(value: T) -> Self {
+            Self { seq: AtomicUsize::new(0), value: UnsafeCell::new(value) }
+        }
+    }
+
+    /// Replaces the value. Fails only if another write is in progress, returning the value back
+    pub fn write(&self, value: T) -> Result<(), T> {
+        let seq = self.seq.load(Relaxed);
+        if seq & 1 != 0 || self.seq.compare_exchange(seq, seq.wrapping_add(1), Relaxed, Relaxed).is_err() {
+            return Err(value);
+        }
+        // The odd sequence has to be visible before any byte of the new value
+        fence(Release);
+
+        // SAFETY: the odd sequence makes us the only writer, readers throw away what they copy meanwhile
+        unsafe { ptr::write_volatile(self.value.get(), value) };
+        self.seq.store(seq.wrapping_add(2), Release);
+        Ok(())
+    }
+
+    /// Copies the value, retrying while it is being written
+    pub fn read(&self) -> T {
+        loop {
+            if let Some(value) = self.try_read() {
+                return value;
+            }
+            core::hint::spin_loop();
+        }
+    }
+
+    /// Copies the value, unless it is being written right now
+    pub fn try_read(&self) -> Option<T> {
+        let seq = self.seq.load(Acquire);
+        if seq & 1 != 0 {
+            return None;
+        }
+
+        // SAFETY: the copy may be torn, so it stays uninit until the sequence confirms it
+        let value = unsafe { ptr::read_volatile(self.value.get().cast::<MaybeUninit<T>>()) };
+        fence(Acquire);
+        // SAFETY: no write happened during the copy
+        (self.seq.load(Relaxed) == seq).then(|| unsafe { value.assume_init() })
+    }
+
+    /// Number of completed writes
+    #[inline]
+    pub fn version(&self) -> usize {
+        self.seq.load(Acquire) / 2
+    }
+}
+
+impl<T: Copy + Default> Default for SeqLock<T> {
+    fn default() -> Self {
+        Self::new(T::default())
+    }
+}
+
+impl<T: Copy + fmt::Debug> fmt::Debug for SeqLock<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("SeqLock").field(&self.try_read()).finish()
+    }
+}
@@ -0,0 +1,94 @@
+use crate::luqueue::{Item, Iter, LUQueue};
+use crate::sync::loom_const_fn;
+use core::cmp::Ordering;
+use core::{fmt, ptr};
+
+/// Intrusive list of [`Item`]s kept sorted by a comparator, the smallest first.
+///
+/// Removal works as in [`LUQueue`], insertion walks to the first item greater than the new one.
+/// Equal items keep their insertion order
+pub struct SortedList<N: 'static> {
+    queue: LUQueue<N>,
+    cmp: fn(&N, &N) -> Ordering,
+}
+
+impl<N: Ord + 'static> SortedList<N> {
+    loom_const_fn! {
+        pub const fn new() -> Self {
+            Self::with_cmp(N::cmp)
+        }
+    }
+}
+
+impl<N: 'static> SortedList<N> {
+    loom_const_fn! {
+        pub const fn with_cmp(cmp: fn(&N, &N) -> Ordering) -> Self {
+            Self { queue: LUQueue::new(), cmp }
+        }
+    }
+
+    #[inline]
+    pub fn count(&self) -> usize {
+        self.queue.count()
+    }
+
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.queue.is_empty()
+    }
+
+    /// Links the item at its place. Returns `Some(true)` if it became the smallest one,
+    /// `None` if it is in the list already or still being removed
+    pub fn insert(&self, node: &'static Item<N>) -> Option<bool> {
+        self.queue.insert_by(node, |item| (self.cmp)(node, item).is_lt())
+    }
+
+    pub fn peek_min(&self) -> Option<&'static Item<N>> {
+        self.queue.into_iter().next()
+    }
+
+    #[inline]
+    pub fn pop_min(&'static self) -> Option<&'static Item<N>> {
+        self.queue.pop_front()
+    }
+
+    /// Pops the smallest item only if `cond` holds for it, e.g. if its deadline has passed
+    pub fn pop_min_if(&'static self, cond: impl Fn(&N) -> bool) -> Option<&'static Item<N>> {
+        loop {
+            let min = self.peek_min().filter(|min| cond(min))?;
+            if let Some(item) = self.queue.pop_impl(|item| ptr::eq(item, min)) {
+                return Some(item);
+            }
+        }
+    }
+
+    #[inline]
+    pub fn remove<T>(&'static self, value: &T) -> Option<&'static Item<N>>
+    where
+        N: PartialEq<T>,
+    {
+        self.queue.pop(value)
+    }
+}
+
+impl<N: Ord + 'static> Default for SortedList<N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<'a, N: 'static> IntoIterator for &'a SortedList<N> {
+    type Item = &'static Item<N>;
+    type IntoIter = Iter<'a, N>;
+
+    #[inline]
+    fn into_iter(self) -> Self::IntoIter {
+        self.queue.into_iter()
+    }
+}
+
+impl<N: fmt::Debug + 'static> fmt::Debug for SortedList<N> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list().entries(self).finish()
+    }
+}
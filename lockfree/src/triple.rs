@@ -0,0 +1,127 @@
+use crate::sync::{loom_const_fn, AtomicBool, AtomicUsize};
+use core::cell::UnsafeCell;
+use core::fmt;
+use core::sync::atomic::Ordering::*;
+
+const INDEX: usize = 0b011;
+/// The middle buffer holds a value the reader hasn't taken yet
+const FRESH: usize = 0b100;
+
+/// Three buffers shared by one writer and one reader.
+///
+/// The writer fills its own buffer and swaps it with the middle one, the reader swaps the middle one
+/// with its own when there is something fresh. Neither side ever waits, and the reader always sees
+/// the whole of the latest value published before its swap
+pub struct TripleBuffer<T> {
+    buffers: [UnsafeCell<Option<T>>; 3],
+    /// Index of the middle buffer and the `FRESH` flag
+    middle: AtomicUsize,
+    split: AtomicBool,
+}
+
+unsafe impl<T: Send> Sync for TripleBuffer<T> {}
+
+impl<T> TripleBuffer<T> {
+    loom_const_fn! {
+        pub const fn new() -> Self {
+            Self {
+                buffers: [UnsafeCell::new(None), UnsafeCell::new(None), UnsafeCell::new(None)],
+                middle: AtomicUsize::new(1),
+                split: AtomicBool::new(false),
+            }
+        }
+    }
+
+    /// Hands out the two halves. Only the first call gets them
+    pub fn try_split(&self) -> Option<(Writer<'_, T>, Reader<'_, T>)> {
+        (!self.split.swap(true, AcqRel)).then_some((Writer { buffer: self, back: 2 }, Reader { buffer: self, front: 0 }))
+    }
+
+    #[inline]
+    fn get(&self, index: usize) -> *mut Option<T> {
+        self.buffers[index].get()
+    }
+}
+
+impl<T> Default for TripleBuffer<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T> fmt::Debug for TripleBuffer<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("TripleBuffer").field("fresh", &(self.middle.load(Relaxed) & FRESH != 0)).finish()
+    }
+}
+
+pub struct Writer<'a, T> {
+    buffer: &'a TripleBuffer<T>,
+    back: usize,
+}
+
+unsafe impl<T: Send> Send for Writer<'_, T> {}
+
+impl<T> Writer<'_, T> {
+    /// Publishes a new value
+    pub fn write(&mut self, value: T) {
+        *self.input() = Some(value);
+        self.publish();
+    }
+
+    /// The writer's own buffer, to build the next value in place.
+    /// It holds whatever was published some writes ago, or nothing
+    #[inline]
+    pub fn input(&mut self) -> &mut Option<T> {
+        // SAFETY: the back buffer belongs to the writer
+        unsafe { &mut *self.buffer.get(self.back) }
+    }
+
+    /// Makes the content of [`Writer::input`] the latest value
+    pub fn publish(&mut self) {
+        self.back = self.buffer.middle.swap(self.back | FRESH, AcqRel) & INDEX;
+    }
+}
+
+impl<T> fmt::Debug for Writer<'_, T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("Writer").field(self.buffer).finish()
+    }
+}
+
+pub struct Reader<'a, T> {
+    buffer: &'a TripleBuffer<T>,
+    front: usize,
+}
+
+unsafe impl<T: Send> Send for Reader<'_, T> {}
+
+impl<T> Reader<'_, T> {
+    /// Takes the latest published value, if there is a newer one than last time
+    pub fn update(&mut self) -> bool {
+        if self.buffer.middle.load(Relaxed) & FRESH == 0 {
+            return false;
+        }
+        self.front = self.buffer.middle.swap(self.front, AcqRel) & INDEX;
+        true
+    }
+
+    /// The latest published value, or `None` if nothing has been published yet
+    pub fn read(&mut self) -> Option<&T> {
+        self.update();
+        self.output().as_ref()
+    }
+
+    /// The value taken by the last [`Reader::update`], without looking for a newer one
+    #[inline]
+    pub fn output(&mut self) -> &mut Option<T> {
+        // SAFETY: the front buffer belongs to the reader
+        unsafe { &mut *self.buffer.get(self.front) }
+    }
+}
+
+impl<T> fmt::Debug for Reader<'_, T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("Reader").field(self.buffer).finish()
+    }
+}
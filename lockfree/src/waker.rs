@@ -0,0 +1,73 @@
+use crate::sync::{loom_const_fn, AtomicUsize};
+use core::cell::UnsafeCell;
+use core::sync::atomic::Ordering::*;
+use core::task::Waker;
+
+const IDLE: usize = 0;
+const REGISTERING: usize = 0b01;
+const WAKING: usize = 0b10;
+
+/// Lock-free single waker slot.
+///
+/// Holds the waker of one waiting task, a new registration replaces the previous one
+pub struct AtomicWaker {
+    state: AtomicUsize,
+    waker: UnsafeCell<Option<Waker>>,
+}
+
+unsafe impl Send for AtomicWaker {}
+unsafe impl Sync for AtomicWaker {}
+
+impl AtomicWaker {
+    loom_const_fn! {
+        pub const fn new() -> Self {
+            Self { state: AtomicUsize::new(IDLE), waker: UnsafeCell::new(None) }
+        }
+    }
+
+    pub fn register(&self, waker: &Waker) {
+        match self.state.compare_exchange(IDLE, REGISTERING, Acquire, Acquire) {
+            Ok(_) => {
+                // SAFETY: `REGISTERING` gives exclusive access to the slot
+                let slot = unsafe { &mut *self.waker.get() };
+                if !slot.as_ref().is_some_and(|old| old.will_wake(waker)) {
+                    *slot = Some(waker.clone());
+                }
+
+                if self.state.compare_exchange(REGISTERING, IDLE, AcqRel, Acquire).is_err() {
+                    // Woken while registering, so the wake is delivered here
+                    let waker = slot.take();
+                    self.state.swap(IDLE, AcqRel);
+                    waker.into_iter().for_each(Waker::wake);
+                }
+            }
+            // Woken right now, or another task is registering. Either way poll again
+            Err(_) => waker.wake_by_ref(),
+        }
+    }
+
+    pub fn wake(&self) {
+        if let Some(waker) = self.take() {
+            waker.wake()
+        }
+    }
+
+    pub fn take(&self) -> Option<Waker> {
+        match self.state.fetch_or(WAKING, AcqRel) {
+            IDLE => {
+                // SAFETY: `WAKING` gives exclusive access to the slot
+                let waker = unsafe { (*self.waker.get()).take() };
+                self.state.fetch_and(!WAKING, Release);
+                waker
+            }
+            // The registering one delivers the wake, or someone else is already waking
+            _ => None,
+        }
+    }
+}
+
+impl Default for AtomicWaker {
+    fn default() -> Self {
+        Self::new()
+    }
+}
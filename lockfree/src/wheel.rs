@@ -0,0 +1,318 @@
+use crate::luqueue::{Item, LUQueue};
+use crate::sync::{loom_const_fn, AtomicUsize};
+use crate::waker::AtomicWaker;
+use core::future::{poll_fn, Future};
+use core::sync::atomic::Ordering::*;
+use core::task::Poll;
+use core::{fmt, ptr};
+
+/// Bits of the tick count each level resolves
+const BITS: u32 = 4;
+const SLOTS: usize = 1 << BITS;
+const SLOT: usize = SLOTS - 1;
+
+/// The command holds the deadline shifted above this bit
+const ARMED: usize = 1;
+/// Deadlines are kept modulo this range, a half of it is the longest delay
+const TIME: usize = usize::MAX >> 1;
+/// The timer is in no bucket
+const NOWHERE: usize = usize::MAX;
+
+struct Entry {
+    /// Deadline and the `ARMED` bit, the only thing changed from outside the tick
+    command: AtomicUsize,
+    period: AtomicUsize,
+    /// Bucket holding the timer. Only the tick touches it
+    slot: AtomicUsize,
+    fired: AtomicUsize,
+    waker: AtomicWaker,
+    callback: Option<fn(&'static Timer)>,
+}
+
+/// Intrusive timer node, driven by a [`TimerWheel`].
+///
+/// When it fires, it wakes the task waiting in [`Timer::wait`] and calls its callback in the tick context.
+/// A timer belongs to one wheel
+#[repr(transparent)]
+pub struct Timer {
+    /// Queued to the wheel by commands, and linked into a bucket by the tick
+    node: Item<Item<Entry>>,
+}
+
+impl Timer {
+    loom_const_fn! {
+        pub const fn new() -> Self {
+            Self::with(None)
+        }
+    }
+
+    loom_const_fn! {
+        pub const fn with_callback(callback: fn(&'static Timer)) -> Self {
+            Self::with(Some(callback))
+        }
+    }
+
+    loom_const_fn! {
+        const fn with(callback: Option<fn(&'static Timer)>) -> Self {
+            let entry = Entry {
+                command: AtomicUsize::new(0),
+                period: AtomicUsize::new(0),
+                slot: AtomicUsize::new(NOWHERE),
+                fired: AtomicUsize::new(0),
+                waker: AtomicWaker::new(),
+                callback,
+            };
+            Self { node: Item::new(Item::new(entry)) }
+        }
+    }
+
+    #[inline]
+    pub fn is_armed(&self) -> bool {
+        self.entry().command.load(SeqCst) & ARMED != 0
+    }
+
+    /// Number of times the timer fired
+    #[inline]
+    pub fn fired(&self) -> usize {
+        self.entry().fired.load(SeqCst)
+    }
+
+    /// Waits until the timer fires after this call. Only one task may wait at a time
+    pub fn wait(&'static self) -> impl Future<Output = ()> {
+        let fired = self.fired();
+        poll_fn(move |cx| {
+            if self.fired() != fired {
+                return Poll::Ready(());
+            }
+            self.entry().waker.register(cx.waker());
+            // It could have fired before the waker was in place
+            if self.fired() != fired {
+                Poll::Ready(())
+            } else {
+                Poll::Pending
+            }
+        })
+    }
+
+    #[inline]
+    fn entry(&self) -> &Entry {
+        &self.node
+    }
+
+    #[inline]
+    fn from_item(item: &'static Item<Entry>) -> &'static Self {
+        // SAFETY: buckets only hold the inner items of timers, which lie at the start of them
+        unsafe { &*ptr::from_ref(item).cast::<Self>() }
+    }
+}
+
+impl Default for Timer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl fmt::Debug for Timer {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let entry = self.entry();
+        let command = entry.command.load(Relaxed);
+        f.debug_struct("Timer")
+            .field("armed", &(command & ARMED != 0))
+            .field("deadline", &(command >> 1))
+            .field("period", &entry.period.load(Relaxed))
+            .field("fired", &entry.fired.load(Relaxed))
+            .finish()
+    }
+}
+
+/// Hierarchical timer wheel for many [`Timer`]s driven by a single tick.
+///
+/// Each of the `LEVELS` levels has 16 buckets, a level spans 16 times the ticks of the one below.
+/// Timers are started, rescheduled and cancelled from any context: that only updates the timer and
+/// queues it for the tick, which alone moves timers between buckets. Delays are in ticks
+pub struct TimerWheel<const LEVELS: usize = 6> {
+    now: AtomicUsize,
+    /// Timers changed since the last tick
+    pending: LUQueue<Item<Entry>>,
+    buckets: [[LUQueue<Entry>; SLOTS]; LEVELS],
+}
+
+impl<const LEVELS: usize> TimerWheel<LEVELS> {
+    #[cfg(not(loom))]
+    pub const fn new() -> Self {
+        const { assert!(LEVELS > 0 && BITS * LEVELS as u32 <= usize::BITS - 2, "TimerWheel levels exceed the time range") };
+        Self {
+            now: AtomicUsize::new(0),
+            pending: LUQueue::new(),
+            buckets: [const { [const { LUQueue::new() }; SLOTS] }; LEVELS],
+        }
+    }
+
+    #[cfg(loom)]
+    pub fn new() -> Self {
+        const { assert!(LEVELS > 0 && BITS * LEVELS as u32 <= usize::BITS - 2, "TimerWheel levels exceed the time range") };
+        Self {
+            now: AtomicUsize::new(0),
+            pending: LUQueue::new(),
+            buckets: core::array::from_fn(|_| core::array::from_fn(|_| LUQueue::new())),
+        }
+    }
+
+    /// Ticks counted so far, wrapping around
+    #[inline]
+    pub fn now(&self) -> usize {
+        self.now.load(SeqCst)
+    }
+
+    /// Fires the timer once, `delay` ticks from now. Restarts it if it is armed
+    #[inline]
+    pub fn start(&'static self, timer: &'static Timer, delay: usize) {
+        self.start_periodic(timer, delay, 0)
+    }
+
+    /// Fires the timer `delay` ticks from now and every `period` ticks after that, or once if `period` is 0
+    pub fn start_periodic(&'static self, timer: &'static Timer, delay: usize, period: usize) {
+        let entry = timer.entry();
+        entry.period.store(period.min(TIME / 2), SeqCst);
+        entry.command.store(self.deadline(delay) << 1 | ARMED, SeqCst);
+        self.pending.push_back(&timer.node);
+    }
+
+    /// Moves the next firing of an armed timer to `delay` ticks from now, keeping its period.
+    /// Returns `false` if the timer is not armed
+    pub fn reschedule(&'static self, timer: &'static Timer, delay: usize) -> bool {
+        let command = &timer.entry().command;
+        let mut current = command.load(SeqCst);
+        loop {
+            if current & ARMED == 0 {
+                return false;
+            }
+            match command.compare_exchange(current, self.deadline(delay) << 1 | ARMED, SeqCst, SeqCst) {
+                Ok(_) => break,
+                Err(changed) => current = changed,
+            }
+        }
+        self.pending.push_back(&timer.node);
+        true
+    }
+
+    /// Disarms the timer. Returns `false` if it was not armed
+    pub fn cancel(&'static self, timer: &'static Timer) -> bool {
+        let armed = timer.entry().command.fetch_and(!ARMED, SeqCst) & ARMED != 0;
+        if armed {
+            // Lets the tick take it out of its bucket
+            self.pending.push_back(&timer.node);
+        }
+        armed
+    }
+
+    /// Starts the timer and waits until it fires. Only one task may wait for a timer at a time
+    pub fn sleep(&'static self, timer: &'static Timer, delay: usize) -> impl Future<Output = ()> {
+        let fired = timer.wait();
+        self.start(timer, delay);
+        fired
+    }
+
+    /// Advances the wheel by one tick and fires the timers that are due.
+    /// Only one context may tick the wheel
+    pub fn tick(&'static self) {
+        let now = self.now.fetch_add(1, SeqCst).wrapping_add(1);
+
+        let mut pending = self.pending.take();
+        while let Some(timer) = pending.next() {
+            self.unlink(timer);
+            self.schedule(timer);
+        }
+
+        // Higher levels first, so their timers may still make it into the current bucket
+        for level in (1..LEVELS).rev() {
+            if now & ((1 << (BITS as usize * level)) - 1) == 0 {
+                self.drain(level, now >> (BITS as usize * level) & SLOT);
+            }
+        }
+        self.drain(0, now & SLOT);
+    }
+
+    #[inline]
+    fn deadline(&self, delay: usize) -> usize {
+        self.now().wrapping_add(delay.min(TIME / 2)) & TIME
+    }
+
+    fn drain(&'static self, level: usize, slot: usize) {
+        let mut bucket = self.buckets[level][slot].take();
+        while let Some(item) = bucket.next() {
+            item.slot.store(NOWHERE, Relaxed);
+            self.schedule(item);
+        }
+    }
+
+    fn unlink(&self, item: &'static Item<Entry>) {
+        let slot = item.slot.swap(NOWHERE, Relaxed);
+        if slot != NOWHERE {
+            self.buckets[slot / SLOTS][slot % SLOTS].pop_impl(|linked| ptr::eq(linked, item));
+        }
+    }
+
+    /// Fires the timer if it is due, or puts it into the bucket of its deadline
+    fn schedule(&self, item: &'static Item<Entry>) {
+        loop {
+            let command = item.command.load(SeqCst);
+            if command & ARMED == 0 {
+                return;
+            }
+
+            let now = self.now();
+            let delta = (command >> 1).wrapping_sub(now) & TIME;
+            if delta != 0 && delta <= TIME / 2 {
+                return self.insert(item, now, delta);
+            }
+
+            let period = item.period.load(SeqCst);
+            let next = match period {
+                0 => command & !ARMED,
+                // Missed periods are skipped
+                _ if (command >> 1).wrapping_add(period).wrapping_sub(now) & TIME > TIME / 2 => {
+                    now.wrapping_add(period) << 1 | ARMED
+                }
+                _ => (command >> 1).wrapping_add(period) << 1 | ARMED,
+            };
+            // Otherwise it was changed meanwhile, and the new command is what counts
+            if item.command.compare_exchange(command, next, SeqCst, SeqCst).is_ok() {
+                Self::fire(item);
+            }
+        }
+    }
+
+    fn insert(&self, item: &'static Item<Entry>, now: usize, delta: usize) {
+        // A timer beyond the span of the wheel is put into the farthest bucket, and moves on from there
+        let delta = delta.min((1 << (BITS as usize * LEVELS)) - 1);
+        let mut level = 0;
+        while level + 1 < LEVELS && delta >> (BITS as usize * (level + 1)) != 0 {
+            level += 1;
+        }
+        let slot = now.wrapping_add(delta) >> (BITS as usize * level) & SLOT;
+
+        self.buckets[level][slot].push_back(item);
+        item.slot.store(level * SLOTS + slot, Relaxed);
+    }
+
+    fn fire(item: &'static Item<Entry>) {
+        item.fired.fetch_add(1, SeqCst);
+        item.waker.wake();
+        if let Some(callback) = item.callback {
+            callback(Timer::from_item(item));
+        }
+    }
+}
+
+impl<const LEVELS: usize> Default for TimerWheel<LEVELS> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<const LEVELS: usize> fmt::Debug for TimerWheel<LEVELS> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("TimerWheel").field("now", &self.now()).finish()
+    }
+}
@@ -0,0 +1,832 @@
+//! Model checks of the lock-free structures.
+//!
+//! Run with `RUSTFLAGS="--cfg loom" cargo test -p varuemb-lockfree --test loom --release`
+#![cfg(loom)]
+
+use loom::thread;
+use varuemb_lockfree::luqueue::{Item, LUQueue};
+
+fn leak<T>(value: T) -> &'static T {
+    Box::leak(Box::new(value))
+}
+
+fn queue<const C: usize>() -> (&'static LUQueue<usize>, [&'static Item<usize>; C]) {
+    let queue = leak(LUQueue::new());
+    let items = core::array::from_fn(|i| leak(Item::new(i)));
+    (queue, items)
+}
+
+fn values(queue: &LUQueue<usize>) -> Vec<usize> {
+    queue.into_iter().map(|item| **item).collect()
+}
+
+mod luqueue {
+    use super::*;
+
+    /// Bounded model for the tests with three threads or two changes per thread, which are too big to check fully
+    fn model(f: impl Fn() + Sync + Send + 'static) {
+        let mut builder = loom::model::Builder::new();
+        builder.preemption_bound = Some(5);
+        builder.check(f);
+    }
+
+    #[test]
+    fn push_push() {
+        loom::model(|| {
+            let (queue, [a, b]) = queue();
+
+            let th = thread::spawn(move || queue.push_back(a));
+            let first = queue.push_back(b).unwrap();
+
+            assert_eq!(th.join().unwrap().unwrap(), !first);
+            assert_eq!(queue.count(), 2);
+            let values = values(queue);
+            assert!(values == [0, 1] || values == [1, 0]);
+        });
+    }
+
+    #[test]
+    fn pop_pop_different() {
+        loom::model(|| {
+            let (queue, [a, b, c]) = queue();
+            for item in [a, b, c] {
+                queue.push_back(item).unwrap();
+            }
+
+            let th = thread::spawn(move || queue.pop(&0).map(|item| **item));
+            assert_eq!(queue.pop(&2).map(|item| **item), Some(2));
+            assert_eq!(th.join().unwrap(), Some(0));
+
+            assert_eq!(values(queue), [1]);
+            assert_eq!(queue.count(), 1);
+            assert_eq!(queue.push_back(a), Some(false));
+            assert_eq!(queue.push_back(c), Some(false));
+            assert_eq!(values(queue), [1, 0, 2]);
+        });
+    }
+
+    #[test]
+    fn pop_pop_neighbours() {
+        loom::model(|| {
+            let (queue, [a, b, c]) = queue();
+            for item in [a, b, c] {
+                queue.push_back(item).unwrap();
+            }
+
+            let th = thread::spawn(move || queue.pop(&1).map(|item| **item));
+            assert_eq!(queue.pop(&2).map(|item| **item), Some(2));
+            assert_eq!(th.join().unwrap(), Some(1));
+
+            assert_eq!(values(queue), [0]);
+            assert_eq!(queue.push_back(b), Some(false));
+            assert_eq!(queue.push_back(c), Some(false));
+            assert_eq!(values(queue), [0, 1, 2]);
+        });
+    }
+
+    #[test]
+    fn pop_pop_same() {
+        loom::model(|| {
+            let (queue, [a, b]) = queue();
+            for item in [a, b] {
+                queue.push_back(item).unwrap();
+            }
+
+            let th = thread::spawn(move || queue.pop(&0).is_some());
+            let popped = queue.pop(&0).is_some();
+
+            assert!(popped ^ th.join().unwrap());
+            assert_eq!(values(queue), [1]);
+            assert_eq!(queue.count(), 1);
+        });
+    }
+
+    #[test]
+    fn push_pop_tail() {
+        loom::model(|| {
+            let (queue, [a, b, c]) = queue();
+            for item in [a, b] {
+                queue.push_back(item).unwrap();
+            }
+
+            let th = thread::spawn(move || queue.push_back(c));
+            assert_eq!(queue.pop(&1).map(|item| **item), Some(1));
+            assert_eq!(th.join().unwrap(), Some(false));
+
+            assert_eq!(values(queue), [0, 2]);
+            assert_eq!(queue.count(), 2);
+        });
+    }
+
+    #[test]
+    fn push_pop_last() {
+        loom::model(|| {
+            let (queue, [a, b]) = queue();
+            queue.push_back(a).unwrap();
+
+            let th = thread::spawn(move || queue.push_back(b));
+            assert_eq!(queue.pop_front().map(|item| **item), Some(0));
+            th.join().unwrap().unwrap();
+
+            assert_eq!(values(queue), [1]);
+            assert_eq!(queue.count(), 1);
+            assert!(!queue.is_empty());
+        });
+    }
+
+    #[test]
+    fn repush_popped() {
+        loom::model(|| {
+            let (queue, [a, b]) = queue();
+            for item in [a, b] {
+                queue.push_back(item).unwrap();
+            }
+
+            let th = thread::spawn(move || queue.pop(&0).and_then(|item| queue.push_back(item)));
+            assert_eq!(queue.pop(&1).map(|item| **item), Some(1));
+            assert!(th.join().unwrap().is_some());
+
+            assert_eq!(values(queue), [0]);
+            assert_eq!(queue.count(), 1);
+        });
+    }
+
+    #[test]
+    fn repush_neighbours() {
+        model(|| {
+            let (queue, [a, b, c]) = queue();
+            for item in [a, b, c] {
+                queue.push_back(item).unwrap();
+            }
+
+            let th = thread::spawn(move || queue.pop(&1).and_then(|item| queue.push_back(item)));
+            queue.pop(&2).and_then(|item| queue.push_back(item)).unwrap();
+            th.join().unwrap().unwrap();
+
+            let mut values = values(queue);
+            values.sort();
+            assert_eq!(values, [0, 1, 2]);
+            assert_eq!(queue.count(), 3);
+        });
+    }
+
+    #[test]
+    fn push_while_popping_neighbours() {
+        model(|| {
+            let (queue, [a, b, c]) = queue();
+            for item in [a, b] {
+                queue.push_back(item).unwrap();
+            }
+
+            let th = thread::spawn(move || queue.pop(&0).map(|item| **item));
+            let push = thread::spawn(move || queue.push_back(c));
+            assert_eq!(queue.pop(&1).map(|item| **item), Some(1));
+            assert_eq!(th.join().unwrap(), Some(0));
+            push.join().unwrap().unwrap();
+
+            assert_eq!(values(queue), [2]);
+            assert_eq!(queue.count(), 1);
+        });
+    }
+
+    #[test]
+    fn push_while_popped() {
+        loom::model(|| {
+            let (queue, [a, b]) = queue();
+            for item in [a, b] {
+                queue.push_back(item).unwrap();
+            }
+
+            let th = thread::spawn(move || queue.push_back(b));
+            assert_eq!(queue.pop(&1).map(|item| **item), Some(1));
+            let pushed = th.join().unwrap();
+
+            // Either the push came too early, or it brought the item back
+            assert_eq!(values(queue), if pushed.is_some() { vec![0, 1] } else { vec![0] });
+            assert_eq!(queue.count(), values(queue).len());
+        });
+    }
+
+    #[test]
+    fn push_take() {
+        loom::model(|| {
+            let (queue, [a, b, c]) = queue();
+            for item in [a, b] {
+                queue.push_back(item).unwrap();
+            }
+
+            let th = thread::spawn(move || queue.push_back(c));
+            let mut taker = queue.take();
+            let taken: Vec<_> = core::iter::from_fn(|| taker.next().map(|item| **item)).collect();
+            drop(taker);
+            th.join().unwrap().unwrap();
+
+            // The push either made it into the taken batch or is left for the next one
+            let left = values(queue);
+            assert!(taken.starts_with(&[0, 1]));
+            assert_eq!([&taken[..], &left[..]].concat(), [0, 1, 2]);
+            assert_eq!(queue.count(), left.len());
+        });
+    }
+
+    #[test]
+    fn repush_while_taking() {
+        loom::model(|| {
+            let (queue, [a, b]) = queue();
+            for item in [a, b] {
+                queue.push_back(item).unwrap();
+            }
+
+            let th = thread::spawn(move || queue.push_back(a));
+            let mut taker = queue.take();
+            let taken: Vec<_> = core::iter::from_fn(|| taker.next().map(|item| **item)).collect();
+            drop(taker);
+            let pushed = th.join().unwrap();
+
+            assert_eq!(taken, [0, 1]);
+            assert_eq!(values(queue), if pushed.is_some() { vec![0] } else { vec![] });
+            assert_eq!(queue.count(), values(queue).len());
+        });
+    }
+
+    #[test]
+    fn pop_during_iteration() {
+        loom::model(|| {
+            let (queue, [a, b, c]) = queue();
+            for item in [a, b, c] {
+                queue.push_back(item).unwrap();
+            }
+
+            let th = thread::spawn(move || queue.pop(&1).map(|item| **item));
+            let seen = values(queue);
+            assert_eq!(th.join().unwrap(), Some(1));
+
+            assert!([&[0, 1, 2][..], &[0, 2], &[0, 1], &[0]].contains(&&seen[..]), "{seen:?}");
+            assert_eq!(values(queue), [0, 2]);
+        });
+    }
+
+    #[test]
+    fn pop_stale_handle() {
+        loom::model(|| {
+            let (queue, [a, b]) = queue();
+            for item in [a, b] {
+                queue.push_back(item).unwrap();
+            }
+            let handle = a.handle();
+
+            let th = thread::spawn(move || queue.pop(&0).and_then(|item| queue.push_back(item)));
+            let popped = queue.pop_handle(handle).map(|item| **item);
+            let repushed = th.join().unwrap();
+
+            // The handle reaches the item only before it is removed the first time
+            assert_eq!(popped.is_some(), repushed.is_none());
+            assert!(handle.get().is_none());
+            assert_eq!(values(queue), if popped.is_some() { vec![1] } else { vec![1, 0] });
+            assert_eq!(queue.count(), values(queue).len());
+        });
+    }
+
+    #[test]
+    fn snapshot_during_repush() {
+        loom::model(|| {
+            let (queue, [a, b, c]) = queue();
+            for item in [a, b, c] {
+                queue.push_back(item).unwrap();
+            }
+
+            let th = thread::spawn(move || {
+                queue.pop(&1).unwrap();
+                queue.push_back(b).unwrap();
+            });
+            let snapshot = queue.snapshot::<3>();
+            th.join().unwrap();
+
+            let seen: Vec<_> = snapshot.iter().map(|item| **item).collect();
+            if snapshot.is_consistent() {
+                assert!([&[0, 1, 2][..], &[0, 2], &[0, 2, 1]].contains(&&seen[..]), "{seen:?}");
+            }
+            assert!(seen.starts_with(&[0]), "{seen:?}");
+            assert_eq!(snapshot.total(), seen.len());
+        });
+    }
+}
+
+mod ring {
+    use loom::future::block_on;
+    use loom::sync::Arc;
+    use loom::thread;
+    use varuemb_lockfree::{AsyncRingQueue, RingQueue};
+
+    #[test]
+    fn push_push() {
+        loom::model(|| {
+            let queue = Arc::new(RingQueue::<usize, 2>::new());
+            queue.try_push(0).unwrap();
+
+            let th = thread::spawn({
+                let queue = queue.clone();
+                move || queue.try_push(1)
+            });
+            let pushed = queue.try_push(2);
+            let pushed = [th.join().unwrap(), pushed];
+
+            // Only one of them fits
+            assert_eq!(pushed.iter().filter(|pushed| pushed.is_ok()).count(), 1);
+            assert_eq!(queue.try_pop(), Some(0));
+            assert!(matches!(queue.try_pop(), Some(1 | 2)));
+            assert_eq!(queue.try_pop(), None);
+        });
+    }
+
+    #[test]
+    fn pop_pop() {
+        loom::model(|| {
+            let queue = Arc::new(RingQueue::<usize, 2>::new());
+            queue.try_push(0).unwrap();
+            queue.try_push(1).unwrap();
+
+            let th = thread::spawn({
+                let queue = queue.clone();
+                move || queue.try_pop()
+            });
+            let popped = queue.try_pop().unwrap();
+            let other = th.join().unwrap().unwrap();
+
+            assert_eq!(popped + other, 1);
+            assert!(queue.is_empty());
+        });
+    }
+
+    #[test]
+    fn push_pop_wrap() {
+        loom::model(|| {
+            let queue = Arc::new(RingQueue::<usize, 2>::new());
+            queue.try_push(0).unwrap();
+
+            let th = thread::spawn({
+                let queue = queue.clone();
+                move || [queue.try_push(1).is_ok(), queue.try_push(2).is_ok()]
+            });
+            let popped: Vec<_> = (0..2).filter_map(|_| queue.try_pop()).collect();
+            let pushed = th.join().unwrap();
+            let left: Vec<_> = core::iter::from_fn(|| queue.try_pop()).collect();
+
+            // Values keep their order across the wrap
+            let mut expected = vec![0];
+            expected.extend([1, 2].into_iter().zip(pushed).filter_map(|(value, pushed)| pushed.then_some(value)));
+            assert!(pushed[0]);
+            assert_eq!([popped, left].concat(), expected);
+        });
+    }
+
+    #[test]
+    fn pop_waits_for_push() {
+        loom::model(|| {
+            let queue = Arc::new(AsyncRingQueue::<usize, 2>::new());
+
+            let th = thread::spawn({
+                let queue = queue.clone();
+                move || block_on(queue.pop())
+            });
+            block_on(queue.push(7));
+
+            assert_eq!(th.join().unwrap(), 7);
+        });
+    }
+
+    #[test]
+    fn push_waits_for_pop() {
+        loom::model(|| {
+            let queue = Arc::new(AsyncRingQueue::<usize, 2>::new());
+            queue.try_push(0).unwrap();
+            queue.try_push(1).unwrap();
+
+            let th = thread::spawn({
+                let queue = queue.clone();
+                move || block_on(queue.push(2))
+            });
+            assert_eq!(block_on(queue.pop()), 0);
+            th.join().unwrap();
+
+            assert_eq!(queue.try_pop(), Some(1));
+            assert_eq!(queue.try_pop(), Some(2));
+        });
+    }
+}
+
+mod bytes {
+    use super::leak;
+    use loom::thread;
+    use varuemb_lockfree::ByteRing;
+
+    /// Streams `0..len` through a ring of 4 bytes, pushing at most `chunk` bytes at once
+    fn stream(len: u8, chunk: usize) {
+        let (mut producer, mut consumer) = leak(ByteRing::<4>::new()).try_split().unwrap();
+
+        let th = thread::spawn(move || {
+            let data: Vec<u8> = (0..len).collect();
+            let mut written = 0;
+            while written < data.len() {
+                match producer.push_slice(&data[written..data.len().min(written + chunk)]) {
+                    0 => thread::yield_now(),
+                    pushed => written += pushed,
+                }
+            }
+        });
+
+        let mut seen = Vec::new();
+        while seen.len() < len as usize {
+            let mut buf = [0; 4];
+            match consumer.pop_slice(&mut buf) {
+                0 => thread::yield_now(),
+                read => seen.extend_from_slice(&buf[..read]),
+            }
+        }
+        th.join().unwrap();
+
+        assert_eq!(seen, (0..len).collect::<Vec<_>>());
+        assert!(consumer.is_empty());
+    }
+
+    /// Spinning halves make the full model explode, so preemptions are bounded
+    fn model(f: impl Fn() + Sync + Send + 'static) {
+        let mut builder = loom::model::Builder::new();
+        builder.preemption_bound = Some(2);
+        builder.check(f);
+    }
+
+    #[test]
+    fn stream_bytes() {
+        model(|| stream(6, 1));
+    }
+
+    #[test]
+    fn stream_chunks() {
+        model(|| stream(7, 3));
+    }
+
+    #[test]
+    fn exact_grant_wraps() {
+        loom::model(|| {
+            let (mut producer, mut consumer) = leak(ByteRing::<4>::new()).try_split().unwrap();
+            producer.grant_exact(2).unwrap().commit(2);
+            consumer.grant().unwrap().release(2);
+
+            let th = thread::spawn(move || {
+                // Two bytes don't fit behind the write index, so the grant starts over
+                let mut grant = producer.grant_exact(2).unwrap();
+                grant.copy_from_slice(&[7, 8]);
+                grant.commit(2);
+            });
+
+            let mut seen = Vec::new();
+            while seen.len() < 2 {
+                match consumer.grant() {
+                    Some(grant) => {
+                        seen.extend_from_slice(&grant);
+                        let len = grant.len();
+                        grant.release(len);
+                    }
+                    None => thread::yield_now(),
+                }
+            }
+            th.join().unwrap();
+
+            assert_eq!(seen, [7, 8]);
+        });
+    }
+
+    #[test]
+    #[cfg(feature = "io")]
+    fn async_read_waits_for_write() {
+        use embedded_io_async::{Read, Write};
+        use loom::future::block_on;
+
+        model(|| {
+            // Not leaked, the registered waker has to be dropped
+            let ring = Box::into_raw(Box::new(ByteRing::<4>::new()));
+            let (mut producer, mut consumer) = unsafe { &*ring }.try_split().unwrap();
+
+            let th = thread::spawn(move || block_on(producer.write(&[1, 2])).unwrap());
+
+            let mut buf = [0; 4];
+            let read = block_on(consumer.read(&mut buf)).unwrap();
+            assert!(read > 0);
+            assert_eq!(buf[..read], [1, 2][..read]);
+            assert_eq!(th.join().unwrap(), 2);
+
+            drop(unsafe { Box::from_raw(ring) });
+        });
+    }
+}
+
+mod pool {
+    use super::leak;
+    use loom::thread;
+    use varuemb_lockfree::pool::{Pool, PoolBox, Stats};
+
+    #[test]
+    fn alloc_alloc_last() {
+        loom::model(|| {
+            let pool = leak(Pool::<usize, 2>::new());
+            let first = pool.alloc(0).unwrap();
+
+            let th = thread::spawn(move || pool.alloc(1));
+            let main = pool.alloc(2);
+            let other = th.join().unwrap();
+
+            // Only one of them gets the slot
+            assert!(main.is_ok() ^ other.is_ok());
+            assert_eq!(*first, 0);
+            assert_eq!(pool.stats(), Stats { capacity: 2, used: 2, peak: 2, exhausted: 1 });
+
+            let value = main.or(other).map(PoolBox::into_inner).unwrap();
+            assert!(value == 1 || value == 2);
+            assert_eq!(pool.stats().used, 1);
+        });
+    }
+
+    #[test]
+    fn free_alloc() {
+        loom::model(|| {
+            let pool = leak(Pool::<usize, 1>::new());
+            let first = pool.alloc(0).unwrap();
+
+            let th = thread::spawn(move || drop(first));
+            let second = pool.alloc(1).ok();
+            th.join().unwrap();
+
+            let value = second.map(|value| *value).or_else(|| pool.alloc(2).ok().map(|value| *value));
+            assert!(matches!(value, Some(1 | 2)));
+            assert_eq!(pool.stats().used, 0);
+        });
+    }
+}
+
+mod snapshot {
+    use super::leak;
+    use loom::thread;
+    use varuemb_lockfree::{SeqLock, TripleBuffer};
+
+    #[test]
+    fn seqlock_read_while_writing() {
+        loom::model(|| {
+            let lock = leak(SeqLock::new((0, 0)));
+
+            let th = thread::spawn(move || {
+                for i in 1..=2 {
+                    lock.write((i, i)).unwrap();
+                }
+            });
+            let seen = lock.try_read();
+            th.join().unwrap();
+
+            // Never torn: both halves come from the same write
+            assert!(matches!(seen, None | Some((0, 0) | (1, 1) | (2, 2))), "{seen:?}");
+            assert_eq!(lock.read(), (2, 2));
+            assert_eq!(lock.version(), 2);
+        });
+    }
+
+    #[test]
+    fn seqlock_write_write() {
+        loom::model(|| {
+            let lock = leak(SeqLock::new(0));
+
+            let th = thread::spawn(move || lock.write(1).is_ok());
+            let main = lock.write(2).is_ok();
+            let other = th.join().unwrap();
+
+            // A write only fails while the other one is in progress
+            assert!(main || other);
+            let value = lock.read();
+            assert!((value == 1 && other) || (value == 2 && main), "{value}");
+        });
+    }
+
+    #[test]
+    fn triple_buffer_latest() {
+        loom::model(|| {
+            let (mut writer, mut reader) = leak(TripleBuffer::new()).try_split().unwrap();
+
+            let th = thread::spawn(move || {
+                for i in 1..=3 {
+                    writer.write([i; 4]);
+                }
+            });
+            let first = reader.read().copied();
+            let second = reader.read().copied();
+            th.join().unwrap();
+
+            // Whole values that never go back in time
+            for value in [first, second].into_iter().flatten() {
+                assert!(value.iter().all(|&v| v == value[0]), "{value:?}");
+            }
+            assert!(first.map_or(0, |value| value[0]) <= second.map_or(0, |value| value[0]));
+            assert_eq!(reader.read(), Some(&[3; 4]));
+            assert!(!reader.update());
+        });
+    }
+}
+
+mod sorted {
+    use super::leak;
+    use loom::thread;
+    use varuemb_lockfree::luqueue::Item;
+    use varuemb_lockfree::SortedList;
+
+    fn list<const C: usize>(keys: [usize; C]) -> (&'static SortedList<usize>, [&'static Item<usize>; C]) {
+        (leak(SortedList::new()), keys.map(|key| leak(Item::new(key))))
+    }
+
+    fn values(list: &SortedList<usize>) -> Vec<usize> {
+        list.into_iter().map(|item| **item).collect()
+    }
+
+    #[test]
+    fn insert_insert() {
+        loom::model(|| {
+            let (list, [a, b, c]) = list([1, 3, 2]);
+            list.insert(b).unwrap();
+
+            let th = thread::spawn(move || list.insert(a));
+            list.insert(c).unwrap();
+            assert!(th.join().unwrap().unwrap());
+
+            assert_eq!(values(list), [1, 2, 3]);
+            assert_eq!(list.count(), 3);
+        });
+    }
+
+    #[test]
+    fn insert_pop_min() {
+        loom::model(|| {
+            let (list, [a, b, c]) = list([1, 3, 2]);
+            list.insert(a).unwrap();
+            list.insert(b).unwrap();
+
+            let th = thread::spawn(move || list.insert(c));
+            assert_eq!(list.pop_min().map(|item| **item), Some(1));
+            th.join().unwrap().unwrap();
+
+            assert_eq!(values(list), [2, 3]);
+            assert_eq!(list.count(), 2);
+        });
+    }
+
+    #[test]
+    fn insert_before_removed() {
+        loom::model(|| {
+            let (list, [a, b, c]) = list([1, 2, 3]);
+            list.insert(a).unwrap();
+            list.insert(c).unwrap();
+
+            // Goes right where the removed item was
+            let th = thread::spawn(move || list.insert(b));
+            assert_eq!(list.remove(&3).map(|item| **item), Some(3));
+            th.join().unwrap().unwrap();
+
+            assert_eq!(values(list), [1, 2]);
+            assert_eq!(list.insert(c), Some(false));
+            assert_eq!(values(list), [1, 2, 3]);
+        });
+    }
+
+    #[test]
+    fn remove_neighbours_reinsert() {
+        loom::model(|| {
+            let (list, [a, b, c]) = list([1, 2, 3]);
+            for item in [a, b, c] {
+                list.insert(item).unwrap();
+            }
+
+            let th = thread::spawn(move || list.remove(&1).map(|item| **item));
+            let popped = list.remove(&2).unwrap();
+            assert_eq!(th.join().unwrap(), Some(1));
+
+            // An item abandoned while its neighbour was removed can't come back in place
+            while list.insert(popped).is_none() {
+                thread::yield_now();
+            }
+            assert_eq!(values(list), [2, 3]);
+            assert_eq!(list.pop_min_if(|min| *min < 3).map(|item| **item), Some(2));
+            assert!(list.pop_min_if(|min| *min < 3).is_none());
+        });
+    }
+}
+
+mod wheel {
+    use super::*;
+    use varuemb_lockfree::wheel::{Timer, TimerWheel};
+
+    fn ticks<const L: usize>(wheel: &'static TimerWheel<L>, count: usize) {
+        for _ in 0..count {
+            wheel.tick();
+        }
+    }
+
+    #[test]
+    fn fires_through_levels() {
+        loom::model(|| {
+            let (wheel, timer) = (leak(TimerWheel::<2>::new()), leak(Timer::new()));
+            wheel.start(timer, 40);
+
+            ticks(wheel, 39);
+            assert_eq!(timer.fired(), 0);
+            wheel.tick();
+            assert_eq!(timer.fired(), 1);
+            assert!(!timer.is_armed());
+        });
+    }
+
+    #[test]
+    fn fires_beyond_span() {
+        loom::model(|| {
+            let (wheel, timer) = (leak(TimerWheel::<1>::new()), leak(Timer::new()));
+            wheel.start(timer, 40);
+
+            ticks(wheel, 39);
+            assert_eq!(timer.fired(), 0);
+            wheel.tick();
+            assert_eq!(timer.fired(), 1);
+        });
+    }
+
+    #[test]
+    fn periodic() {
+        loom::model(|| {
+            let (wheel, timer) = (leak(TimerWheel::<1>::new()), leak(Timer::new()));
+            wheel.start_periodic(timer, 2, 3);
+
+            ticks(wheel, 7);
+            assert_eq!(timer.fired(), 2);
+            wheel.tick();
+            assert_eq!(timer.fired(), 3);
+
+            assert!(wheel.reschedule(timer, 1));
+            wheel.tick();
+            assert_eq!(timer.fired(), 4);
+            assert!(wheel.cancel(timer));
+            ticks(wheel, 4);
+            assert_eq!(timer.fired(), 4);
+        });
+    }
+
+    #[test]
+    fn start_while_ticking() {
+        loom::model(|| {
+            let (wheel, timer) = (leak(TimerWheel::<1>::new()), leak(Timer::new()));
+
+            let th = thread::spawn(move || wheel.start(timer, 1));
+            ticks(wheel, 2);
+            th.join().unwrap();
+
+            // The delay counts from whenever the start saw the time
+            ticks(wheel, 2);
+            assert_eq!(timer.fired(), 1);
+            assert!(!timer.is_armed());
+        });
+    }
+
+    #[test]
+    fn cancel_while_ticking() {
+        loom::model(|| {
+            let (wheel, timer) = (leak(TimerWheel::<1>::new()), leak(Timer::new()));
+            wheel.start(timer, 1);
+
+            let th = thread::spawn(move || wheel.cancel(timer));
+            wheel.tick();
+            let cancelled = th.join().unwrap();
+
+            wheel.tick();
+            assert_eq!(timer.fired(), !cancelled as usize);
+            assert!(!timer.is_armed());
+        });
+    }
+
+    #[test]
+    fn sleep_until_fired() {
+        use loom::future::block_on;
+
+        let mut builder = loom::model::Builder::new();
+        builder.preemption_bound = Some(2);
+        builder.check(|| {
+            let wheel = leak(TimerWheel::<1>::new());
+            // Not leaked, the registered waker has to be dropped
+            let raw = Box::into_raw(Box::new(Timer::new()));
+            let timer = unsafe { &*raw };
+
+            let th = thread::spawn(move || {
+                while timer.fired() == 0 {
+                    wheel.tick();
+                    thread::yield_now();
+                }
+            });
+            block_on(wheel.sleep(timer, 1));
+            th.join().unwrap();
+
+            drop(unsafe { Box::from_raw(raw) });
+        });
+    }
+}
@@ -0,0 +1,165 @@
+//! Renders the graph exported by the notifier macros as Graphviz DOT.
+//!
+//! Usage: `notifier-graph [DIR] > graph.dot`, where `DIR` defaults to `$VARUEMB_NOTIFIER_GRAPH`
+//! and holds a directory per crate
+
+use serde_json::Value;
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt::Write as _;
+use std::path::{Path, PathBuf};
+
+/// Last segment of a path, which is how the macros of different items refer to each other
+fn short(path: &str) -> &str {
+    let path = path.split('<').next().unwrap_or(path);
+    path.rsplit("::").next().unwrap_or(path)
+}
+
+fn field(value: &Value, key: &str) -> String {
+    value[key].as_str().unwrap_or_default().to_owned()
+}
+
+fn load(dir: &Path) -> std::io::Result<BTreeMap<String, Vec<Value>>> {
+    let mut nodes = BTreeMap::<String, Vec<Value>>::new();
+    load_into(dir, &mut nodes)?;
+    Ok(nodes)
+}
+
+fn load_into(dir: &Path, nodes: &mut BTreeMap<String, Vec<Value>>) -> std::io::Result<()> {
+    for entry in std::fs::read_dir(dir)? {
+        let path = entry?.path();
+        if path.is_dir() {
+            load_into(&path, nodes)?;
+            continue;
+        }
+        if path.extension().map_or(true, |ext| ext != "json") {
+            continue;
+        }
+        let Some(kind) = path
+            .file_name()
+            .and_then(|name| name.to_str()?.split('.').next().map(str::to_owned))
+        else {
+            continue;
+        };
+        match serde_json::from_str(&std::fs::read_to_string(&path)?) {
+            Ok(node) => nodes.entry(kind).or_default().push(node),
+            Err(err) => eprintln!("Skipping {}: {err}", path.display()),
+        }
+    }
+    Ok(())
+}
+
+fn render(nodes: &BTreeMap<String, Vec<Value>>) -> String {
+    let empty = Vec::new();
+    let of = |kind: &str| nodes.get(kind).unwrap_or(&empty);
+    let providers = of("rpc")
+        .iter()
+        .map(|rpc| short(&field(rpc, "service")).to_owned())
+        .collect::<BTreeSet<_>>();
+
+    let mut dot =
+        String::from("digraph notifier {\n    rankdir=LR;\n    node [fontname=\"monospace\"];\n");
+    for notifier in of("notifier") {
+        let name = field(notifier, "name");
+        let _ = writeln!(
+            dot,
+            "    subgraph \"cluster_{name}\" {{\n        label=\"{name}\";"
+        );
+        for service in notifier["services"].as_array().unwrap_or(&empty) {
+            let _ = writeln!(dot, "        \"{}\";", short(&field(service, "service")));
+        }
+        dot.push_str("    }\n");
+    }
+
+    let mut events = BTreeSet::new();
+    let mut edges = BTreeSet::new();
+    // Publishing edges come from the services, which declare everything they publish
+    for event in of("event") {
+        events.insert(field(event, "name"));
+    }
+    for mixer in of("mixer") {
+        let _ = writeln!(dot, "    \"{}\" [shape=diamond];", field(mixer, "name"));
+    }
+    for service in of("service") {
+        let name = field(service, "name");
+        let rpc = if providers.contains(&name) || service["rpc"].is_string() {
+            "\\nrpc provider"
+        } else {
+            ""
+        };
+        let _ = writeln!(
+            dot,
+            "    \"{name}\" [shape=box, label=\"{name} x{}{rpc}\"];",
+            field(service, "count")
+        );
+
+        for publish in service["publishes"].as_array().unwrap_or(&empty) {
+            let event = short(&field(publish, "event")).to_owned();
+            let mut attrs = Vec::new();
+            if publish["retain"].as_bool() == Some(true) {
+                attrs.push("retain");
+            }
+            if publish["protected"].as_bool() == Some(true) {
+                attrs.push("protected");
+            }
+            edges.insert(format!(
+                "\"{name}\" -> \"{event}\" [label=\"{}\"]",
+                attrs.join(", ")
+            ));
+            events.insert(event);
+        }
+        for subscribe in service["subscribes"].as_array().unwrap_or(&empty) {
+            let kind = field(subscribe, "kind");
+            let count = field(subscribe, "count");
+            match kind.as_str() {
+                "rpc" => {
+                    let provider = short(&field(subscribe, "service")).to_owned();
+                    edges.insert(format!(
+                        "\"{name}\" -> \"{provider}\" [style=dashed, label=\"rpc x{count}\"]"
+                    ));
+                }
+                "request" => {}
+                _ => {
+                    let event = short(&field(subscribe, "event")).to_owned();
+                    let target = match subscribe["mixer"].as_str() {
+                        Some(mixer) => {
+                            edges.insert(format!("\"{}\" -> \"{name}\"", short(mixer)));
+                            short(mixer).to_owned()
+                        }
+                        None => name.clone(),
+                    };
+                    edges.insert(format!(
+                        "\"{event}\" -> \"{target}\" [label=\"{kind} x{count}\"]"
+                    ));
+                    events.insert(event);
+                }
+            }
+        }
+    }
+
+    for event in events {
+        let _ = writeln!(dot, "    \"{event}\" [shape=ellipse];");
+    }
+    for edge in edges {
+        let _ = writeln!(dot, "    {edge};");
+    }
+    dot.push_str("}\n");
+    dot
+}
+
+fn main() {
+    let Some(dir) = std::env::args_os()
+        .nth(1)
+        .map(PathBuf::from)
+        .or_else(|| std::env::var_os("VARUEMB_NOTIFIER_GRAPH").map(PathBuf::from))
+    else {
+        eprintln!("Usage: notifier-graph [DIR], DIR defaults to $VARUEMB_NOTIFIER_GRAPH");
+        std::process::exit(2);
+    };
+    match load(&dir) {
+        Ok(nodes) => print!("{}", render(&nodes)),
+        Err(err) => {
+            eprintln!("Failed to read {}: {err}", dir.display());
+            std::process::exit(1);
+        }
+    }
+}
@@ -1,3 +1,4 @@
+use crate::graph;
 use crate::proc_meta_parser::Parser;
 use proc_macro2::Ident;
 use quote::{quote, ToTokens};
@@ -63,6 +64,17 @@ impl<'a> ToTokens for Event<'a> {
             ..
         } = self;
 
+        let name = ident.to_string();
+        tokens.extend(graph::export(
+            if *is_mixer { "mixer" } else { "event" },
+            &name,
+            serde_json::json!({
+                "name": name,
+                "notifier": graph::label(notif),
+                "service": service.as_ref().map(graph::label),
+            }),
+        ));
+
         let out = if *is_mixer {
             quote! {
                 impl #_crate ::pubsub::mixer::Mixer<#notif> for #ident {}
@@ -0,0 +1,40 @@
+//! Export of the pub/sub and RPC graph.
+//!
+//! If `$VARUEMB_NOTIFIER_GRAPH` is set, every macro writes the node it describes as a JSON file into it. The
+//! `notifier-graph` binary renders them as DOT.
+//!
+//! The files of a crate go into a directory named after it. The macros only write, so a node removed from
+//! the code lingers until its file is removed, e.g. by clearing the directory before a build. The macros
+//! read the variable through `option_env!` in the expanded crate, so cargo rebuilds it once it changes
+
+use proc_macro2::TokenStream;
+use quote::{quote, ToTokens};
+use serde_json::Value;
+use std::path::PathBuf;
+
+pub const DIR_ENV: &str = "VARUEMB_NOTIFIER_GRAPH";
+
+/// Directory the graph is written to, if any
+pub fn dir() -> Option<PathBuf> {
+    std::env::var_os(DIR_ENV).map(PathBuf::from)
+}
+
+/// Writes the node, failures are ignored since the graph is only a debug aid. The returned tokens make the
+/// expanded crate depend on the variable
+pub fn export(kind: &str, name: &str, node: Value) -> TokenStream {
+    if let Some(dir) = dir() {
+        let krate = std::env::var("CARGO_CRATE_NAME").unwrap_or_else(|_| "unknown".to_owned());
+        let dir = dir.join(krate);
+        if std::fs::create_dir_all(&dir).is_ok() {
+            let _ = std::fs::write(dir.join(format!("{kind}.{name}.json")), node.to_string());
+        }
+    }
+    quote! {
+        const _: ::core::option::Option<&str> = ::core::option_env!(#DIR_ENV);
+    }
+}
+
+/// Tokens as they are written, without the spaces of the token stream
+pub fn label(tokens: &impl ToTokens) -> String {
+    tokens.to_token_stream().to_string().replace(' ', "")
+}
@@ -8,6 +8,7 @@ use quote::{quote, ToTokens};
 use syn::Error;
 
 mod event;
+mod graph;
 mod notifier;
 mod rpc;
 mod service;
@@ -1,3 +1,4 @@
+use crate::graph;
 use crate::proc_meta_parser::Parser;
 use heck::ToUpperCamelCase;
 use proc_macro2::TokenStream;
@@ -62,6 +63,19 @@ impl<'a> ToTokens for Notifier<'a> {
             })
             .collect::<Vec<_>>();
 
+        let services = self_fields.iter().map(|field| {
+            serde_json::json!({
+                "field": field.ident.as_ref().map(ToString::to_string),
+                "service": graph::label(&field.ty),
+            })
+        });
+        let name = ident.to_string();
+        tokens.extend(graph::export(
+            "notifier",
+            &name,
+            serde_json::json!({ "name": name, "services": services.collect::<Vec<_>>() }),
+        ));
+
         // Notifier
         tokens.extend({
             let vis = &self.item.vis;
@@ -195,6 +209,32 @@ impl<'a> ToTokens for Notifier<'a> {
             }
         }));
 
+        //Impl Topology
+        tokens.extend({
+            let services = self_fields
+                .iter()
+                .enumerate()
+                .flat_map(|(i, field)| {
+                    let field_ident = &field.ident;
+                    let attrs = &fields_attrs[i];
+                    quote!(
+                        #attrs
+                        __visitor(&self. #field_ident);
+                    )
+                })
+                .collect::<TokenStream>();
+            quote! {
+                impl #_crate ::introspect::Topology for #ident {
+                    fn services(
+                        &'static self,
+                        __visitor: &mut dyn FnMut(&'static dyn #_crate ::introspect::ServiceInfo),
+                    ) {
+                        #services
+                    }
+                }
+            }
+        });
+
         //Impl NotifierEvent
         tokens.extend({
             let calc = self_fields
@@ -5,6 +5,7 @@ use syn::{spanned::Spanned, Error};
 
 pub struct Parser {
     keys: Vec<String>,
+    flags: Vec<String>,
     span: proc_macro2::Span,
     output: LinkedHashMap<String, TokenStream>,
 }
@@ -17,20 +18,34 @@ impl Parser {
             span,
             output: Default::default(),
             keys: keys.into_iter().map(ToString::to_string).collect(),
+            flags: Vec::new(),
         }
     }
 
+    /// Boolean keys that may also be given bare, standing for `flag = true`
+    pub fn flags<'a, V: IntoIterator<Item = &'a T>, T: ToString + ?Sized + 'a>(
+        mut self,
+        flags: V,
+    ) -> Self {
+        self.flags = flags.into_iter().map(ToString::to_string).collect();
+        self
+    }
+
     pub fn parse(&mut self, meta: syn::meta::ParseNestedMeta) -> syn::parse::Result<()> {
         for key in self.keys.iter() {
             if meta.path.is_ident(key) {
-                let to_parse = meta.value()?;
-                
-                let value = if to_parse.fork().parse::<syn::Expr>().is_ok() {
-                    to_parse.parse::<syn::Expr>()?.into_token_stream()
-                } else if to_parse.fork().parse::<syn::Type>().is_ok() {
-                    to_parse.parse::<syn::Type>()?.into_token_stream()
+                let value = if self.flags.contains(key) && !meta.input.peek(syn::Token![=]) {
+                    quote::quote!(true)
                 } else {
-                    return Err(meta.error(format!("Error parsing: {key}")))
+                    let to_parse = meta.value()?;
+
+                    if to_parse.fork().parse::<syn::Expr>().is_ok() {
+                        to_parse.parse::<syn::Expr>()?.into_token_stream()
+                    } else if to_parse.fork().parse::<syn::Type>().is_ok() {
+                        to_parse.parse::<syn::Type>()?.into_token_stream()
+                    } else {
+                        return Err(meta.error(format!("Error parsing: {key}")))
+                    }
                 };
                 if self.output.insert(key.clone(), value).is_some() {
                     return Err(syn::Error::new(
@@ -1,3 +1,4 @@
+use crate::graph;
 use crate::proc_meta_parser::Parser;
 use proc_macro2::TokenStream;
 use quote::{quote, ToTokens};
@@ -11,6 +12,7 @@ pub struct Rpc<'a> {
     request: (Ident, bool),
     response: (Ident, bool),
     error: Option<Type>,
+    handler: Ident,
     notif: Path,
 }
 
@@ -28,6 +30,7 @@ impl<'a> Rpc<'a> {
                 "error",
                 "no_debug_request",
                 "no_debug_response",
+                "handler",
             ],
             attr.span(),
         );
@@ -35,6 +38,10 @@ impl<'a> Rpc<'a> {
             .expect("Parse M");
 
         let parse = parse::Parse::new(input)?;
+        let handler = match parser.get("handler") {
+            Ok(handler) => handler,
+            Err(_) => Self::default_handler(&parse.service)?,
+        };
         let this = Self {
             meta,
             parse,
@@ -52,9 +59,30 @@ impl<'a> Rpc<'a> {
             ),
             notif: parser.get("notifier")?,
             error: parser.get("error").ok(),
+            handler,
         };
         Ok(this)
     }
+
+    /// `<Service>Handler`, named after the last segment of the service path
+    fn default_handler(service: &Type) -> Result<Ident, Error> {
+        let Type::Path(path) = service else {
+            return Err(Error::new(
+                service.span(),
+                "Expected a path, or a `handler` name",
+            ));
+        };
+        let Some(last) = path.path.segments.last() else {
+            return Err(Error::new(
+                service.span(),
+                "Expected a path, or a `handler` name",
+            ));
+        };
+        Ok(Ident::new(
+            &format!("{}Handler", last.ident),
+            last.ident.span(),
+        ))
+    }
 }
 
 impl ToTokens for Rpc<'_> {
@@ -67,6 +95,19 @@ impl ToTokens for Rpc<'_> {
         let (resp, resp_dbg) = &self.response;
         let err = &self.error;
 
+        let name = graph::label(service);
+        tokens.extend(graph::export(
+            "rpc",
+            &name,
+            serde_json::json!({
+                "service": name,
+                "notifier": graph::label(notif),
+                "request": req.to_string(),
+                "response": resp.to_string(),
+                "handlers": self.parse.handlers.keys().map(ToString::to_string).collect::<Vec<_>>(),
+            }),
+        ));
+
         #[rustfmt::skip]
         let generate_fn = |
             ident: &Ident,
@@ -74,7 +115,8 @@ impl ToTokens for Rpc<'_> {
             parse::SigBase { input, output, .. }: &_,
             not_default: Option<&TokenStream>,
             response: Option<&TokenStream>,
-            duration: Option<&Expr>
+            duration: Option<&Expr>,
+            retry: Option<&Expr>
         | {
             let inputs = input
                 .iter()
@@ -99,6 +141,11 @@ impl ToTokens for Rpc<'_> {
             } else {
                 quote!(::core::option::Option::Some(#_crate ::Duration::from_secs(10)))
             };
+            let retry = if let Some(retry) = retry {
+                quote!(::core::option::Option::Some(#retry))
+            } else {
+                quote!(::core::option::Option::None)
+            };
             if let Some(output) = output.as_ref() {
                 quote! {
                     pub async fn #raw_ident (
@@ -110,6 +157,7 @@ impl ToTokens for Rpc<'_> {
                         self.0.process(
                             #req,
                             #duration,
+                            #retry,
                             ::core::result::Result::Ok(|__resp| match __resp {
                                 #resp :: #ident (__ret) => #resp_map,
                                 _ => ::core::option::Option::None,
@@ -132,6 +180,58 @@ impl ToTokens for Rpc<'_> {
             }
         };
 
+        let generate_stream_fn = |ident: &Ident, sig: &parse::SigBase, stream: parse::Stream| {
+            let parse::SigBase {
+                raw_ident,
+                duration,
+                retry,
+                input,
+                output,
+            } = sig;
+            let inputs = input
+                .iter()
+                .flat_map(|p| {
+                    let attrs = &p.attrs;
+                    let ident = &p.pat;
+                    quote!(#(#attrs)* #ident ,)
+                })
+                .collect::<TokenStream>();
+            let duration = match duration {
+                Some(duration) => quote!(#_crate ::Duration::from_millis(#duration)),
+                None => quote!(#_crate ::Duration::from_secs(10)),
+            };
+            let timeout = match stream {
+                parse::Stream::PerItem => quote!(#_crate ::rpc::StreamTimeout::PerItem(#duration)),
+                parse::Stream::Overall => quote!(#_crate ::rpc::StreamTimeout::Overall(#duration)),
+            };
+            let retry = match retry {
+                Some(retry) => quote!(::core::option::Option::Some(#retry)),
+                None => quote!(::core::option::Option::None),
+            };
+            quote! {
+                pub async fn #raw_ident (
+                    &self,
+                    #(#input,)*
+                ) -> #_crate ::rpc::Result<
+                    #_crate ::rpc::ResponseStream<#_crate ::GetPubSub<#notif, #service>, #output>,
+                    #_crate ::GetPubSub<#notif, #service>,
+                >
+                {
+                    self.0.process_stream(
+                        #req :: #ident { #inputs },
+                        ::core::option::Option::Some(#timeout),
+                        #retry,
+                        |__resp| match __resp {
+                            #resp :: #ident (__ret) => ::core::option::Option::Some(__ret),
+                            #[allow(unreachable_patterns)]
+                            _ => ::core::option::Option::None,
+                        },
+                    )
+                    .await
+                }
+            }
+        };
+
         // Request
         tokens.extend({
             let (indexes, fields): (TokenStream, TokenStream) = self
@@ -183,6 +283,11 @@ impl ToTokens for Rpc<'_> {
                     let Some(out) = sig.base.output.as_ref() else {
                         return None;
                     };
+                    // A stream ends with `None`
+                    let out = match sig.stream {
+                        Some(_) => quote!(::core::option::Option<#out>),
+                        None => quote!(#out),
+                    };
                     Some((
                         quote!(#resp :: #ident (_) => #i,),
                         quote! { #ident (#out), },
@@ -254,6 +359,7 @@ impl ToTokens for Rpc<'_> {
                                 sig @ parse::SigBase {
                                     raw_ident,
                                     duration,
+                                    retry,
                                     ..
                                 },
                             interface:
@@ -262,11 +368,22 @@ impl ToTokens for Rpc<'_> {
                                     alias: new_ident,
                                     commands: interface,
                                 },
-                            ..
+                            stream,
                         },
                     )| {
+                        if let Some(stream) = stream {
+                            return (generate_stream_fn(ident, sig, *stream), quote!());
+                        }
                         let raw = new_ident.as_ref().unwrap_or(raw_ident);
-                        let def = generate_fn(ident, raw, sig, None, None, duration.as_ref());
+                        let def = generate_fn(
+                            ident,
+                            raw,
+                            sig,
+                            None,
+                            None,
+                            duration.as_ref(),
+                            retry.as_ref(),
+                        );
                         if interface.is_empty() {
                             return (def, quote!());
                         }
@@ -282,6 +399,7 @@ impl ToTokens for Rpc<'_> {
                                          sig @ parse::SigBase {
                                              raw_ident,
                                              duration: interface_duration,
+                                             retry: interface_retry,
                                              ..
                                          },
                                      block,
@@ -294,6 +412,7 @@ impl ToTokens for Rpc<'_> {
                                         Some(block),
                                         response.as_ref(),
                                         interface_duration.as_ref().or(duration.as_ref()),
+                                        interface_retry.as_ref().or(retry.as_ref()),
                                     );
                                     quote!(#r#fn)
                                 },
@@ -323,6 +442,104 @@ impl ToTokens for Rpc<'_> {
             }
         });
 
+        // Handler trait and its dispatch, an abandoned request drops its handler
+        if !self.parse.handlers.is_empty() {
+            let handler = &self.handler;
+            let _err = quote!(<#service as #_crate ::rpc::traits::RpcProvider<#notif>>::Error);
+            let (methods, arms): (TokenStream, TokenStream) = self
+                .parse
+                .handlers
+                .iter()
+                .map(|(ident, sig)| {
+                    let parse::SigBase {
+                        raw_ident,
+                        input,
+                        output,
+                        ..
+                    } = &sig.base;
+                    let names = input.iter().map(|p| &p.pat).collect::<Vec<_>>();
+                    match (output, sig.stream) {
+                        (Some(output), Some(_)) => (
+                            quote! {
+                                async fn #raw_ident (
+                                    &mut self,
+                                    sender: &mut #_crate ::rpc::ResponseSender<'_, #_crate ::GetPubSub<#notif, #service>, #output>,
+                                    #(#input,)*
+                                ) -> ::core::result::Result<(), #_err>;
+                            },
+                            quote! {
+                                #req :: #ident { #(#names,)* } => {
+                                    let mut __sender = __request.stream(#resp :: #ident);
+                                    let __ret = __request.handle(__handler.#raw_ident(&mut __sender, #(#names,)*)).await;
+                                    let _ = match __ret {
+                                        ::core::option::Option::Some(::core::result::Result::Ok(())) => __sender.end().await,
+                                        ::core::option::Option::Some(::core::result::Result::Err(__err)) => __sender.error(__err),
+                                        ::core::option::Option::None => ::core::result::Result::Ok(()),
+                                    };
+                                }
+                            },
+                        ),
+                        (Some(output), None) => (
+                            quote! {
+                                async fn #raw_ident (&mut self, #(#input,)*) -> ::core::result::Result<#output, #_err>;
+                            },
+                            quote! {
+                                #req :: #ident { #(#names,)* } => {
+                                    let _ = match __request.handle(__handler.#raw_ident(#(#names,)*)).await {
+                                        ::core::option::Option::Some(::core::result::Result::Ok(__ret)) => {
+                                            __request.response(#resp :: #ident (__ret)).await
+                                        }
+                                        ::core::option::Option::Some(::core::result::Result::Err(__err)) => {
+                                            __request.response_err(__err)
+                                        }
+                                        ::core::option::Option::None => ::core::result::Result::Ok(()),
+                                    };
+                                }
+                            },
+                        ),
+                        (None, _) => (
+                            quote! {
+                                async fn #raw_ident (&mut self, #(#input,)*);
+                            },
+                            quote! {
+                                #req :: #ident { #(#names,)* } => {
+                                    __request.handle(__handler.#raw_ident(#(#names,)*)).await;
+                                }
+                            },
+                        ),
+                    }
+                })
+                .unzip();
+            let doc =
+                format!("Handlers of the RPC requests of `{name}`, served by `{name}::serve_rpc`");
+            tokens.extend(quote! {
+                #[doc = #doc]
+                #[allow(async_fn_in_trait, dead_code)]
+                pub trait #handler {
+                    #methods
+                }
+            });
+            out.extend(quote! {
+                impl #service {
+                    /// Answers the requests of the subscriber with the handler, forever. A handler is dropped
+                    /// as soon as the caller cancels its request
+                    #[allow(dead_code)]
+                    pub async fn serve_rpc(
+                        __subscriber: &mut #_crate ::rpc::GetRpcSubscriber<Self, #notif>,
+                        __handler: &mut impl #handler,
+                    ) {
+                        loop {
+                            let mut __request =
+                                <Self as #_crate ::service::traits::Service<#notif>>::rpc_request(__subscriber).await;
+                            match __request.take() {
+                                #arms
+                            }
+                        }
+                    }
+                }
+            });
+        }
+
         tokens.extend(quote!(const _: () = { #out };));
     }
 }
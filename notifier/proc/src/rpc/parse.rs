@@ -15,6 +15,7 @@ struct Data {
 pub struct SigBase {
     pub raw_ident: Ident,
     pub duration: Option<Expr>,
+    pub retry: Option<Expr>,
     pub input: Vec<PatType>,
     pub output: Option<Type>,
 }
@@ -67,6 +68,7 @@ impl Interface {
     fn make_sig(item: syn::ItemFn) -> Result<InterfaceSignature, Error> {
         let mut response = None;
         let mut duration = None;
+        let mut retry = None;
 
         for attr in item.attrs {
             let attr_span = attr.path().span();
@@ -87,16 +89,25 @@ impl Interface {
                     return Err(Error::new(meta_span, "Duration already exist"));
                 }
                 duration = Some(syn::parse2::<Expr>(tokens)?);
+            } else if attr.path().is_ident("retry") {
+                let syn::Meta::List(syn::MetaList { tokens, .. }) = attr.meta else {
+                    return Err(Error::new(meta_span, "Incorrect attribute"));
+                };
+                if retry.is_some() {
+                    return Err(Error::new(meta_span, "Retry already exist"));
+                }
+                retry = Some(syn::parse2::<Expr>(tokens)?);
             } else {
                 return Err(Error::new(
                     attr_span,
-                    "Support only \"response\" or \"duration\" attribute",
+                    "Support only \"response\", \"duration\" or \"retry\" attribute",
                 ));
             }
         }
         let base = SigBase {
             raw_ident: item.sig.ident,
             duration,
+            retry,
             input: item
                 .sig
                 .inputs
@@ -128,10 +139,37 @@ impl Interface {
     }
 }
 
+/// Timeout of a streaming handler, the duration applies to each item or to the whole stream
+#[derive(Debug, Clone, Copy)]
+pub enum Stream {
+    PerItem,
+    Overall,
+}
+impl Stream {
+    fn parse(attr: &Attribute) -> Result<Self, Error> {
+        if !matches!(attr.meta, Meta::List(_)) {
+            return Ok(Self::PerItem);
+        }
+        let mut parser = Parser::new(["overall"], attr.span()).flags(["overall"]);
+        attr.parse_nested_meta(|meta| parser.parse(meta))?;
+        let overall = parser
+            .get("overall")
+            .ok()
+            .as_ref()
+            .is_some_and(LitBool::value);
+        Ok(if overall {
+            Self::Overall
+        } else {
+            Self::PerItem
+        })
+    }
+}
+
 #[derive(Debug)]
 pub struct Signature {
     pub base: SigBase,
     pub interface: Interface,
+    pub stream: Option<Stream>,
 }
 
 pub struct ItemFn {
@@ -185,13 +223,15 @@ impl Parse {
             let mut no_response = None;
             let mut interface = None;
             let mut duration = None;
+            let mut retry = None;
+            let mut stream = None;
             for attr in attrs {
                 if attr.path().is_ident("rpc_handler") {
                     if !matches!(attr.meta, Meta::List(_)) {
                         continue;
                     }
                     let mut parser = Parser::new(
-                        ["alias", "response", "no_response", "duration"],
+                        ["alias", "response", "no_response", "duration", "retry"],
                         attr.span(),
                     );
                     attr.parse_nested_meta(|meta| parser.parse(meta))?;
@@ -204,6 +244,7 @@ impl Parse {
                     }
                     no_response = parser.get("no_response").ok().as_ref().map(LitBool::value);
                     duration = parser.get("duration").ok();
+                    retry = parser.get("retry").ok();
                 } else if attr.path().is_ident("rpc_handler_setup") {
                     let mut parser = Parser::new(["name", "skip", "alias"], attr.span());
                     attr.parse_nested_meta(|meta| parser.parse(meta))?;
@@ -226,16 +267,23 @@ impl Parse {
                         return Err(Error::new(attr.span(), "Interface already define"));
                     }
                     interface = Some(Interface::parse(&attr)?)
+                } else if attr.path().is_ident("stream") {
+                    if stream.is_some() {
+                        return Err(Error::new(attr.span(), "Stream already define"));
+                    }
+                    stream = Some((Stream::parse(&attr)?, attr.span()));
                 }
             }
             let key = Ident::new(
                 &sig.ident.to_string().to_upper_camel_case(),
                 sig.ident.span(),
             );
+            let span = sig.span();
             let sig = Signature {
                 base: SigBase {
                     raw_ident: sig.ident,
                     duration,
+                    retry,
                     input: sig
                         .inputs
                         .into_iter()
@@ -273,7 +321,19 @@ impl Parse {
                     .filter(|_| no_response.map_or(true, |v| !v)),
                 },
                 interface: interface.unwrap_or_default(),
+                stream: stream.map(|(stream, _)| stream),
             };
+            if let Some((_, attr_span)) = stream {
+                if sig.base.output.is_none() {
+                    return Err(Error::new(span, "Stream handler needs an item type"));
+                }
+                if !sig.interface.commands.is_empty() || sig.interface.alias.is_some() {
+                    return Err(Error::new(
+                        attr_span,
+                        "Stream handler doesn't support an interface",
+                    ));
+                }
+            }
             this.handlers.insert(key, sig);
             if let Some((name, (_, span))) = data.front() {
                 return Err(Error::new(*span, format!("Parameter {name}, not found")));
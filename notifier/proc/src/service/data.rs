@@ -1,25 +1,56 @@
+use crate::graph;
 use crate::proc_meta_parser::Parser;
 use linked_hash_map::LinkedHashMap;
 use quote::ToTokens;
 use syn::{spanned::Spanned, Attribute, Error, Expr, Ident, LitBool, Path};
 
+/// What to do when an event can never reach a subscriber, `warn` by default
+#[derive(Debug, Clone, Copy)]
+pub enum Dangling {
+    Deny,
+    Warn,
+    Allow,
+}
+
+impl Dangling {
+    fn parse(parser: &mut Parser) -> Result<Self, Error> {
+        match parser.get::<Ident>("dangling").ok() {
+            None => Ok(Self::Warn),
+            Some(level) if level == "deny" => Ok(Self::Deny),
+            Some(level) if level == "warn" => Ok(Self::Warn),
+            Some(level) if level == "allow" => Ok(Self::Allow),
+            Some(level) => Err(Error::new(
+                level.span(),
+                format!("Unsupported dangling '{level}', expected 'deny', 'warn' or 'allow'"),
+            )),
+        }
+    }
+}
+
 #[derive(Debug)]
 pub struct PublisherData {
     pub protected: Option<LitBool>,
+    pub retain: Option<Ident>,
+    pub dangling: Dangling,
 }
 
 #[derive(Debug)]
 pub enum SubscriberType {
     PubSub,
+    Latest,
     Rpc,
 }
 
 #[derive(Debug)]
 pub struct SubscriberData {
+    /// Name of the subscription for introspection
+    pub label: String,
     pub count: Expr,
+    pub overflow: Option<Ident>,
     pub name: Ident,
     pub mixed: Option<(Path, Expr)>,
     pub ty: SubscriberType,
+    pub dangling: Dangling,
 }
 
 #[derive(Debug)]
@@ -50,14 +81,23 @@ impl Data {
                 this.count = parser.get("count").ok();
                 this.rpc = parser.get("rpc").ok();
             } else if attr.path().is_ident("notifier_publisher") {
-                let mut parser = Parser::new(["event", "protected"], attr.span());
+                let mut parser =
+                    Parser::new(["event", "protected", "retain", "dangling"], attr.span())
+                        .flags(["protected", "retain"]);
                 attr.parse_nested_meta(|meta| parser.parse(meta))?;
 
+                let key = parser.get::<Path>("event")?;
+                let retain = parser
+                    .get::<LitBool>("retain")
+                    .is_ok_and(|retain| retain.value);
                 let data = PublisherData {
                     protected: parser.get("protected").ok(),
+                    retain: retain.then(|| {
+                        Ident::new(&format!("_retain_{}", this.publishers.len()), key.span())
+                    }),
+                    dangling: Dangling::parse(&mut parser)?,
                 };
 
-                let key = parser.get::<Path>("event")?;
                 let span = key.span();
                 let event = key.to_token_stream().to_string();
                 if this.publishers.insert(key, data).is_some() {
@@ -67,10 +107,63 @@ impl Data {
                     ));
                 }
             } else if attr.path().is_ident("notifier_subscriber") {
-                let mut parser =
-                    Parser::new(["event", "count", "mixer", "mix_mapper"], attr.span());
+                let mut parser = Parser::new(
+                    [
+                        "event",
+                        "count",
+                        "mode",
+                        "overflow",
+                        "mixer",
+                        "mix_mapper",
+                        "dangling",
+                    ],
+                    attr.span(),
+                );
                 attr.parse_nested_meta(|meta| parser.parse(meta))?;
 
+                let (ty, count) = match parser.get::<Ident>("mode").ok() {
+                    None => (SubscriberType::PubSub, parser.get("count")?),
+                    Some(mode) if mode == "queue" => (SubscriberType::PubSub, parser.get("count")?),
+                    Some(mode) if mode == "latest" => {
+                        if parser.get::<Expr>("count").is_ok() {
+                            return Err(Error::new(
+                                mode.span(),
+                                "Attribute 'count' is not supported with mode = latest",
+                            ));
+                        }
+                        (SubscriberType::Latest, syn::parse_quote!(1usize))
+                    }
+                    Some(mode) => {
+                        return Err(Error::new(
+                            mode.span(),
+                            format!("Unsupported mode '{mode}', expected 'queue' or 'latest'"),
+                        ))
+                    }
+                };
+
+                let overflow = match parser.get::<Ident>("overflow").ok() {
+                    None => None,
+                    Some(_) if matches!(ty, SubscriberType::Latest) => {
+                        return Err(Error::new(
+                            attr.span(),
+                            "Attribute 'overflow' is not supported with mode = latest",
+                        ))
+                    }
+                    Some(policy) if policy == "drop_oldest" => {
+                        Some(Ident::new("DropOldest", policy.span()))
+                    }
+                    Some(policy) if policy == "drop_newest" => {
+                        Some(Ident::new("DropNewest", policy.span()))
+                    }
+                    Some(policy) if policy == "error" => Some(Ident::new("Error", policy.span())),
+                    Some(policy) => {
+                        return Err(Error::new(
+                            policy.span(),
+                            format!("Unsupported overflow '{policy}', expected 'drop_oldest', 'drop_newest' or 'error'"),
+                        ))
+                    }
+                };
+
                 let mixer = parser.get("mixer");
                 let mix_mapper = parser.get("mix_mapper");
                 let mixed = match (mixer, mix_mapper) {
@@ -81,10 +174,13 @@ impl Data {
 
                 let key = parser.get::<Path>("event")?;
                 let data = SubscriberData {
-                    count: parser.get("count")?,
-                    ty: SubscriberType::PubSub,
+                    label: graph::label(&key),
+                    count,
+                    overflow,
+                    ty,
                     name: Ident::new(&format!("_{}", this.subscribers.len()), key.span()),
                     mixed,
+                    dangling: Dangling::parse(&mut parser)?,
                 };
 
                 let span = key.span();
@@ -101,10 +197,13 @@ impl Data {
 
                 let key = parser.get::<Path>("service")?;
                 let data = SubscriberData {
+                    label: format!("Response<{}>", graph::label(&key)),
                     count: parser.get("count")?,
+                    overflow: None,
                     ty: SubscriberType::Rpc,
                     name: Ident::new(&format!("_{}", this.subscribers.len()), key.span()),
                     mixed: None,
+                    dangling: Dangling::Allow,
                 };
 
                 let span = key.span();
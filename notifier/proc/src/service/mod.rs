@@ -1,5 +1,6 @@
+use crate::graph;
 use proc_macro2::{Span, TokenStream};
-use quote::{quote, ToTokens};
+use quote::{quote, quote_spanned, ToTokens};
 use syn::{spanned::Spanned, DeriveInput, Error, Ident};
 
 pub mod data;
@@ -21,10 +22,13 @@ impl<'a> Service<'a> {
             data.subscribers.insert(
                 syn::parse_quote!(#_crate ::rpc::GetRequest<Self, #ident>),
                 data::SubscriberData {
+                    label: format!("Request<{ident}>"),
                     count: rpc.clone(),
+                    overflow: None,
                     name: Ident::new("_rpc", rpc.span()),
                     mixed: None,
                     ty: data::SubscriberType::PubSub,
+                    dangling: data::Dangling::Allow,
                 },
             );
         }
@@ -32,6 +36,47 @@ impl<'a> Service<'a> {
         Ok(Self { meta, data, ident })
     }
 
+    fn export_graph(&self) -> TokenStream {
+        let publishes = self.data.publishers.iter().map(|(path, data)| {
+            serde_json::json!({
+                "event": graph::label(path),
+                "protected": data.protected.as_ref().is_some_and(|value| value.value),
+                "retain": data.retain.is_some(),
+            })
+        });
+        let subscribes = self.data.subscribers.iter().map(|(path, data)| {
+            let (kind, event) = match &data.ty {
+                data::SubscriberType::PubSub if data.name == "_rpc" => ("request", None),
+                data::SubscriberType::PubSub => ("queue", Some(graph::label(path))),
+                data::SubscriberType::Latest => ("latest", Some(graph::label(path))),
+                data::SubscriberType::Rpc => ("rpc", None),
+            };
+            serde_json::json!({
+                "kind": kind,
+                "event": event,
+                "service": matches!(data.ty, data::SubscriberType::Rpc).then(|| graph::label(path)),
+                "count": match data.ty {
+                    data::SubscriberType::Latest => "1".into(),
+                    _ => graph::label(&data.count),
+                },
+                "mixer": data.mixed.as_ref().map(|(mixer, _)| graph::label(mixer)),
+            })
+        });
+        let name = self.ident.to_string();
+        graph::export(
+            "service",
+            &name,
+            serde_json::json!({
+                "name": name,
+                "notifier": graph::label(&self.data.notifier),
+                "count": self.data.count.as_ref().map_or("1".into(), graph::label),
+                "rpc": self.data.rpc.as_ref().map(graph::label),
+                "publishes": publishes.collect::<Vec<_>>(),
+                "subscribes": subscribes.collect::<Vec<_>>(),
+            }),
+        )
+    }
+
     fn generate(&self) -> TokenStream {
         let _impl = Ident::new("__impl", Span::mixed_site());
         let _crate = &self.meta.crate_ident;
@@ -68,6 +113,9 @@ impl<'a> Service<'a> {
                         data::SubscriberType::PubSub => {
                             quote!(#_crate ::pubsub::Subscription<Self, #path, { #count }>)
                         }
+                        data::SubscriberType::Latest => {
+                            quote!(#_crate ::pubsub::Latest<Self, #path>)
+                        }
                         data::SubscriberType::Rpc => {
                             quote!(#_crate ::rpc::Subscription<Self, #path, { #count }>)
                         }
@@ -75,10 +123,25 @@ impl<'a> Service<'a> {
                     quote! { #name: #ty,}
                 })
                 .collect::<TokenStream>();
+            let retained = self
+                .data
+                .publishers
+                .iter()
+                .filter_map(|(path, data)| {
+                    let name = data.retain.as_ref()?;
+                    Some(quote! { #name: #_crate ::pubsub::Retained<Self, #path>, })
+                })
+                .collect::<TokenStream>();
+            // Remembers a cancel for each request the instance may hold, the queued ones and the handled one
+            let cancellation = self.data.rpc.as_ref().map(|count| {
+                quote! { _cancellation: #_crate ::rpc::Cancellation<{ #count + 1 }>, }
+            });
             quote! {
                 #[allow(non_camel_case_types)]
                 pub struct #_impl {
                     #fields
+                    #retained
+                    #cancellation
                 }
             }
         });
@@ -92,8 +155,14 @@ impl<'a> Service<'a> {
                 .flat_map(|(_, data)| {
                     let name = &data.name;
                     match &data.ty {
-                        data::SubscriberType::PubSub => {
-                            quote!( #name: #_crate ::pubsub::Subscription::default(),)
+                        data::SubscriberType::PubSub => match &data.overflow {
+                            Some(overflow) => quote!(
+                                #name: #_crate ::pubsub::Subscription::with_overflow(#_crate ::pubsub::Overflow::#overflow),
+                            ),
+                            None => quote!( #name: #_crate ::pubsub::Subscription::default(),),
+                        },
+                        data::SubscriberType::Latest => {
+                            quote!( #name: #_crate ::pubsub::Latest::default(),)
                         }
                         data::SubscriberType::Rpc => {
                             quote!( #name: #_crate ::rpc::Subscription::default(),)
@@ -101,15 +170,40 @@ impl<'a> Service<'a> {
                     }
                 })
                 .collect::<TokenStream>();
+            let retained = self
+                .data
+                .publishers
+                .values()
+                .filter_map(|data| {
+                    let name = data.retain.as_ref()?;
+                    Some(quote!( #name: #_crate ::pubsub::Retained::default(),))
+                })
+                .collect::<TokenStream>();
+            let cancellation = self
+                .data
+                .rpc
+                .as_ref()
+                .map(|_| quote!( _cancellation: #_crate ::rpc::Cancellation::default(),));
             quote! {
                 impl const #_crate ::pubsub::traits::PubSub for #_impl {
                     type Service = #_ident;
                     type Notifier = #_notif;
-                    const NEW: Self = Self { #fields };
+                    const NEW: Self = Self { #fields #retained #cancellation };
                 }
             }
         });
 
+        // Impl Cancellable
+        out.extend(self.data.rpc.as_ref().map(|_| {
+            quote! {
+                impl #_crate ::rpc::traits::Cancellable for #_impl {
+                    fn __cancellation(&'static self) -> ::core::option::Option<&'static dyn #_crate ::rpc::traits::CancelNotices> {
+                        ::core::option::Option::Some(&self._cancellation)
+                    }
+                }
+            }
+        }));
+
         // Impl Publisher
         out.extend(
             self.data
@@ -121,9 +215,17 @@ impl<'a> Service<'a> {
                             const PROTECTED: ::core::primitive::bool = #value;
                         )
                     });
+                    let retained = data.retain.as_ref().map(|name| {
+                        quote!(
+                            fn __retained(&self) -> ::core::option::Option<&#_crate ::pubsub::Retained<Self, #path>> {
+                                ::core::option::Option::Some(&self. #name)
+                            }
+                        )
+                    });
                     quote! {
                         impl #_crate ::pubsub::traits::Publisher<#path> for #_impl {
                             #protected
+                            #retained
                         }
                     }
                 })
@@ -137,18 +239,24 @@ impl<'a> Service<'a> {
                 .iter()
                 .flat_map(|(path, data)| {
                     let ret = match &data.ty {
-                        data::SubscriberType::PubSub => quote!(#_crate ::pubsub::traits::GetSubscriberRet<Self::Notifier, #path>),
+                        data::SubscriberType::PubSub | data::SubscriberType::Latest => {
+                            quote!(#_crate ::pubsub::traits::GetSubscriberRet<Self::Notifier, #path>)
+                        }
                         data::SubscriberType::Rpc => quote!(#_crate ::rpc::traits::GetSubscriberRet<Self::Notifier, #path>),
                     };
                     let path = match &data.ty {
-                        data::SubscriberType::PubSub => path.to_token_stream(),
+                        data::SubscriberType::PubSub | data::SubscriberType::Latest => {
+                            path.to_token_stream()
+                        }
                         data::SubscriberType::Rpc => {
                             quote!(#_crate ::rpc::GetResponse<Self, #path>)
                         }
                     };
                     let name = &data.name;
                     let field = match &data.ty {
-                        data::SubscriberType::PubSub => quote!(&self. #name),
+                        data::SubscriberType::PubSub | data::SubscriberType::Latest => {
+                            quote!(&self. #name)
+                        }
                         data::SubscriberType::Rpc => quote!(&*self. #name),
                     };
                     quote! {
@@ -163,6 +271,70 @@ impl<'a> Service<'a> {
                 .collect::<TokenStream>()
         });
 
+        // Impl Introspect
+        out.extend({
+            let publishes = self.data.publishers.keys().map(graph::label);
+            let subscribes = self.data.subscribers.values().map(|data| &data.label);
+            let subscriptions = self
+                .data
+                .subscribers
+                .iter()
+                .flat_map(|(path, data)| {
+                    let label = &data.label;
+                    let path = match &data.ty {
+                        data::SubscriberType::PubSub | data::SubscriberType::Latest => {
+                            path.to_token_stream()
+                        }
+                        data::SubscriberType::Rpc => {
+                            quote!(#_crate ::rpc::GetResponse<Self, #path>)
+                        }
+                    };
+                    quote! {
+                        __visitor(#_crate ::introspect::Subscription::new(
+                            #label,
+                            #_crate ::pubsub::traits::Subscriber::<#path>::__get(self),
+                        ));
+                    }
+                })
+                .collect::<TokenStream>();
+            quote! {
+                impl #_crate ::introspect::Introspect for #_impl {
+                    const PUBLISHES: &'static [&'static str] = &[#(#publishes),*];
+                    const SUBSCRIBES: &'static [&'static str] = &[#(#subscribes),*];
+
+                    fn __subscriptions(
+                        &'static self,
+                        __visitor: &mut dyn FnMut(#_crate ::introspect::Subscription),
+                    ) {
+                        #subscriptions
+                    }
+                }
+            }
+        });
+
+        // Dangling checks
+        out.extend({
+            let service = self.ident.to_string();
+            let notifier = graph::label(_notif);
+            let publishers = self.data.publishers.iter().map(|(path, data)| {
+                let message = format!(
+                    "Event `{}` is published by `{service}` but no service of `{notifier}` subscribes to it",
+                    graph::label(path)
+                );
+                let cond = quote!(#_crate ::calc::published::<#_notif, #path>());
+                dangling_check(data.dangling, path.span(), cond, message)
+            });
+            let subscribers = self.data.subscribers.iter().map(|(path, data)| {
+                let message = format!(
+                    "Event `{}` is subscribed by `{service}` but no service of `{notifier}` can deliver it to it",
+                    data.label
+                );
+                let cond = quote!(#_crate ::calc::delivered::<#_notif, #_ident, #path>());
+                dangling_check(data.dangling, path.span(), cond, message)
+            });
+            publishers.chain(subscribers).collect::<TokenStream>()
+        });
+
         let mixed = self
             .data
             .subscribers
@@ -223,6 +395,17 @@ impl<'a> Service<'a> {
                     quote!( .or_else(|| __mixed.#name.try_map()) )
                 })
                 .collect::<TokenStream>();
+            let poll_mixed = mix_iter
+                .clone()
+                .flat_map(|(_, data)| {
+                    let name = &data.name;
+                    quote! {
+                        if let ::core::task::Poll::Ready(__e) = __mixed.#name.poll_map(__cx) {
+                            return ::core::task::Poll::Ready(__e);
+                        }
+                    }
+                })
+                .collect::<TokenStream>();
 
             quote! {
                 impl<M> #_crate ::pubsub::mixer::SubscriberMixer<M> for #_impl
@@ -243,6 +426,14 @@ impl<'a> Service<'a> {
                     fn __try_mixed(__mixed: &mut Self::Mixed) -> Option<#_crate ::event::Event<Self::Notifier, M>> {
                         None #try_mixed
                     }
+
+                    fn __poll_mixed(
+                        __mixed: &mut Self::Mixed,
+                        __cx: &mut ::core::task::Context<'_>,
+                    ) -> ::core::task::Poll<#_crate ::event::Event<Self::Notifier, M>> {
+                        #poll_mixed
+                        ::core::task::Poll::Pending
+                    }
                 }
             }
         });
@@ -262,9 +453,50 @@ impl<'a> Service<'a> {
     }
 }
 
+/// Const-evaluated check that fails with `message` unless `cond` holds, or warns through the
+/// `unused_must_use` lint, which can be allowed like any other
+fn dangling_check(
+    level: data::Dangling,
+    span: Span,
+    cond: TokenStream,
+    message: String,
+) -> TokenStream {
+    let check = match level {
+        data::Dangling::Deny => quote! {
+            const fn check() {
+                ::core::panic!("{}", #message)
+            }
+        },
+        data::Dangling::Warn => quote! {
+            #[must_use = #message]
+            const fn check() -> __Dangling {
+                __Dangling
+            }
+        },
+        data::Dangling::Allow => return TokenStream::default(),
+    };
+    quote_spanned! {span=>
+        const _: () = {
+            struct __Check<const OK: ::core::primitive::bool>;
+            #[allow(dead_code)]
+            struct __Dangling;
+            #[allow(dead_code)]
+            impl __Check<true> {
+                const fn check() {}
+            }
+            #[allow(dead_code)]
+            impl __Check<false> {
+                #check
+            }
+            __Check::<{ #cond }>::check();
+        };
+    }
+}
+
 impl<'a> ToTokens for Service<'a> {
     fn to_tokens(&self, tokens: &mut TokenStream) {
         let _crate = &self.meta.crate_ident;
+        tokens.extend(self.export_graph());
         let out = self.generate();
         tokens.extend(quote! {
             use #_crate ::service::traits::Service as _;
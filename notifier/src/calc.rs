@@ -100,3 +100,145 @@ impl CountID {
         Self(0, 0)
     }
 }
+
+/// Whether any service of the notifier subscribes to the event
+///
+/// A publisher nobody subscribes to fails to build with `dangling = deny`
+/// ```compile_fail
+/// # #![allow(incomplete_features)]
+/// # #![feature(const_trait_impl, generic_const_exprs, specialization)]
+/// use varuemb::notifier::{notifier, Event, Service};
+///
+/// #[notifier]
+/// pub struct Notif {
+///     sensor: Sensor,
+/// }
+///
+/// #[derive(Service)]
+/// #[notifier_service(notifier = Notif)]
+/// #[notifier_publisher(event = Level, dangling = deny)]
+/// pub struct Sensor;
+///
+/// #[derive(Event, Clone, Debug)]
+/// #[notifier_event(notifier = Notif, service = Sensor)]
+/// pub struct Level;
+/// # fn main() {}
+/// ```
+/// and warns through `unused_must_use` by default
+/// ```compile_fail
+/// # #![deny(unused_must_use)]
+/// # #![allow(incomplete_features)]
+/// # #![feature(const_trait_impl, generic_const_exprs, specialization)]
+/// use varuemb::notifier::{notifier, Event, Service};
+///
+/// #[notifier]
+/// pub struct Notif {
+///     sensor: Sensor,
+/// }
+///
+/// #[derive(Service)]
+/// #[notifier_service(notifier = Notif)]
+/// #[notifier_publisher(event = Level)]
+/// pub struct Sensor;
+///
+/// #[derive(Event, Clone, Debug)]
+/// #[notifier_event(notifier = Notif, service = Sensor)]
+/// pub struct Level;
+/// # fn main() {}
+/// ```
+/// unless the check is allowed
+/// ```
+/// # #![deny(unused_must_use)]
+/// # #![allow(incomplete_features)]
+/// # #![feature(const_trait_impl, generic_const_exprs, specialization)]
+/// use varuemb::notifier::{notifier, Event, Service};
+///
+/// #[notifier]
+/// pub struct Notif {
+///     sensor: Sensor,
+/// }
+///
+/// #[derive(Service)]
+/// #[notifier_service(notifier = Notif)]
+/// #[notifier_publisher(event = Level, dangling = allow)]
+/// pub struct Sensor;
+///
+/// #[derive(Event, Clone, Debug)]
+/// #[notifier_event(notifier = Notif, service = Sensor)]
+/// pub struct Level;
+/// # fn main() {}
+/// ```
+pub const fn published<N, E>() -> bool
+where
+    N: traits::NotifierServiceEvent<E>,
+    E: __evt::Event<N, Service: __svc::Service<N>>,
+{
+    N::ID_COUNT != 0
+}
+
+/// Whether the event can reach the subscriptions of the service: the service owning the event has instances
+/// and a protected event is delivered only to that service
+///
+/// A subscription to an event protected by another service fails to build with `dangling = deny`
+/// ```compile_fail
+/// # #![allow(incomplete_features)]
+/// # #![feature(const_trait_impl, generic_const_exprs, specialization)]
+/// use varuemb::notifier::{notifier, Event, Service};
+///
+/// #[notifier]
+/// pub struct Notif {
+///     sensor: Sensor,
+///     display: Display,
+/// }
+///
+/// #[derive(Service)]
+/// #[notifier_service(notifier = Notif)]
+/// #[notifier_publisher(event = Level, protected)]
+/// pub struct Sensor;
+///
+/// #[derive(Service)]
+/// #[notifier_service(notifier = Notif)]
+/// #[notifier_subscriber(event = Level, count = 1, dangling = deny)]
+/// pub struct Display;
+///
+/// #[derive(Event, Clone, Debug)]
+/// #[notifier_event(notifier = Notif, service = Sensor)]
+/// pub struct Level;
+/// # fn main() {}
+/// ```
+/// and warns through `unused_must_use` by default
+/// ```compile_fail
+/// # #![deny(unused_must_use)]
+/// # #![allow(incomplete_features)]
+/// # #![feature(const_trait_impl, generic_const_exprs, specialization)]
+/// use varuemb::notifier::{notifier, Event, Service};
+///
+/// #[notifier]
+/// pub struct Notif {
+///     sensor: Sensor,
+///     display: Display,
+/// }
+///
+/// #[derive(Service)]
+/// #[notifier_service(notifier = Notif)]
+/// #[notifier_publisher(event = Level, protected)]
+/// pub struct Sensor;
+///
+/// #[derive(Service)]
+/// #[notifier_service(notifier = Notif)]
+/// #[notifier_subscriber(event = Level, count = 1)]
+/// pub struct Display;
+///
+/// #[derive(Event, Clone, Debug)]
+/// #[notifier_event(notifier = Notif, service = Sensor)]
+/// pub struct Level;
+/// # fn main() {}
+/// ```
+pub const fn delivered<N, S, E>() -> bool
+where
+    N: traits::NotifierService<S> + traits::NotifierService<E::Service>,
+    S: __pub::IsSubscribed<N, E> + __svc::Service<N>,
+    E: __evt::Event<N, Service: __svc::Service<N>> + __pub::IsPublisher<S::Impl>,
+{
+    crate::is_pubsub_impl::<S, N, E>() && crate::count::<N, E::Service>() != 0 && !crate::is_protected::<S::Impl, E>()
+}
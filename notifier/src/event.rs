@@ -1,5 +1,8 @@
+use core::cell::Cell;
 use core::marker::PhantomData;
-use embassy_time::Duration;
+use core::sync::atomic::{AtomicUsize, Ordering};
+use embassy_sync::blocking_mutex::{raw, Mutex};
+use embassy_time::{Duration, Instant};
 
 pub struct Event<N, E> {
     pub(crate) data: E,
@@ -16,7 +19,7 @@ impl<N, E: core::fmt::Debug> core::fmt::Debug for Event<N, E> {
 }
 
 impl<N: crate::traits::Notifier, E> Event<N, E> {
-    pub(crate) fn new_pubsub<P>(pubsub: &crate::pubsub::PubSub<P>, data: E) -> (Self, usize)
+    pub(crate) fn new_pubsub<P>(pubsub: &crate::pubsub::PubSub<P>, data: E, context: &TraceContext) -> (Self, usize)
     where
         E: traits::Event<N>,
         P: crate::pubsub::traits::PubSub<Notifier = N> + crate::pubsub::traits::CanMetadata,
@@ -25,7 +28,14 @@ impl<N: crate::traits::Notifier, E> Event<N, E> {
         let event_id = pubsub.incr_event_id();
         let event = Self {
             data,
-            meta: Metadata { id: event_id, src: pubsub.metadata(), dst: pubsub.metadata() },
+            meta: Metadata {
+                id: event_id,
+                src: pubsub.metadata(),
+                dst: pubsub.metadata(),
+                dropped: false,
+                timestamp: Instant::now(),
+                trace: context.next(),
+            },
             _phantom: Default::default(),
         };
         (event, event_id)
@@ -39,6 +49,20 @@ impl<N: crate::traits::Notifier, E> Event<N, E> {
         self.meta
     }
 
+    /// Whether older events were overwritten by this one before the subscriber got them
+    pub fn dropped(&self) -> bool {
+        self.meta.dropped
+    }
+
+    /// When the event was published
+    pub fn timestamp(&self) -> Instant {
+        self.meta.timestamp
+    }
+
+    pub fn trace(&self) -> Trace {
+        self.meta.trace
+    }
+
     pub fn map<M>(self, mapper: impl FnOnce(E) -> M) -> Event<N, M> {
         Event { data: (mapper)(self.data), meta: self.meta, _phantom: Default::default() }
     }
@@ -50,8 +74,10 @@ impl<N: crate::traits::Notifier, E> Event<N, E> {
         let target = crate::log_target(self.meta.src.name());
         log::info!(
             target: &target,
-            "Publishing<id: {}>: {:?}",
+            "Publishing<id: {}, trace: {}, at: {}ms>: {:?}",
             self.meta.id,
+            self.meta.trace,
+            self.meta.timestamp.as_millis(),
             self.data
         )
     }
@@ -60,8 +86,9 @@ impl<N: crate::traits::Notifier, E> Event<N, E> {
         let target = crate::log_target(self.meta.src.name());
         log::debug!(
             target: &target,
-            "<id: {}> to {}",
+            "<id: {}, trace: {}> to {}",
             self.meta.id,
+            self.meta.trace,
             self.meta.dst
         )
     }
@@ -73,9 +100,10 @@ impl<N: crate::traits::Notifier, E> Event<N, E> {
         let target = crate::log_target(self.meta.src.name());
         log::error!(
             target: &target,
-            "Event {:?} with id {} doesn't sent to {}, cause it's full",
+            "Event {:?} with id {} (trace {}) doesn't sent to {}, cause it's full",
             self.data,
             self.meta.id,
+            self.meta.trace,
             self.meta.dst,
         )
     }
@@ -96,6 +124,9 @@ pub struct Metadata {
     pub(crate) id: usize,
     pub(crate) src: &'static crate::Metadata,
     pub(crate) dst: &'static crate::Metadata,
+    pub(crate) dropped: bool,
+    pub(crate) timestamp: Instant,
+    pub(crate) trace: Trace,
 }
 
 impl core::fmt::Debug for Metadata {
@@ -104,28 +135,58 @@ impl core::fmt::Debug for Metadata {
             .field("event_id", &self.id)
             .field("src", &format_args!("{}", self.src))
             .field("dst", &format_args!("{}", self.dst))
+            .field("dropped", &self.dropped)
+            .field("timestamp", &self.timestamp)
+            .field("trace", &format_args!("{}", self.trace))
             .finish()
     }
 }
 
 impl Metadata {
+    /// Service instance that published the event
+    pub fn src(&self) -> &'static crate::Metadata {
+        self.src
+    }
+
+    /// Service instance the event is sent to
+    pub fn dst(&self) -> &'static crate::Metadata {
+        self.dst
+    }
+
     pub(crate) fn print_publish_err_timeout(&self, timeout: Duration) {
         let target = crate::log_target(self.src.name());
         log::error!(
             target: &target,
-            "Event<{}> doesn't sent to {}, cause timeout {}",
+            "Event<{}> (trace {}) doesn't sent to {}, cause timeout {}",
             self.id,
+            self.trace,
             self.dst,
             timeout
         )
     }
 
+    pub(crate) fn print_publish_retry(&self, kind: &str, attempt: u32, attempts: u32, delay: Duration) {
+        let target = crate::log_target(self.src.name());
+        log::error!(
+            target: &target,
+            "Event<{}> (trace {}) doesn't sent to {}, cause {}, retry {}/{} in {}",
+            self.id,
+            self.trace,
+            self.dst,
+            kind,
+            attempt + 1,
+            attempts,
+            delay
+        )
+    }
+
     pub(crate) fn print_publish_err_inactive(&self) {
         let target = crate::log_target(self.src.name());
         log::error!(
             target: &target,
-            "Event<{}> doesn't sent to {}, cause it's inactive",
+            "Event<{}> (trace {}) doesn't sent to {}, cause it's inactive",
             self.id,
+            self.trace,
             self.dst,
         )
     }
@@ -141,6 +202,97 @@ impl Metadata {
     // }
 }
 
+/// Causal position of an event: the chain it belongs to, its own span and the span that caused it
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Trace {
+    id: usize,
+    span: usize,
+    parent: Option<usize>,
+}
+
+impl Trace {
+    /// Trace of a recorded event, see `recorder`
+    pub const fn new(id: usize, span: usize, parent: Option<usize>) -> Self {
+        Self { id, span, parent }
+    }
+
+    /// Span of the event that started the chain
+    pub fn id(&self) -> usize {
+        self.id
+    }
+
+    /// Unique id of the event across the notifier
+    pub fn span(&self) -> usize {
+        self.span
+    }
+
+    /// Span of the event the publishing service handled, `None` for the first event of the chain
+    pub fn parent(&self) -> Option<usize> {
+        self.parent
+    }
+}
+
+impl core::fmt::Display for Trace {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self.parent {
+            Some(parent) => write!(f, "{}:{}>{}", self.id, parent, self.span),
+            None => write!(f, "{}:{}", self.id, self.span),
+        }
+    }
+}
+
+/// Trace of the event a service instance handles, the events it publishes continue it.
+///
+/// A received event is handled until its subscriber is polled again, what is published meanwhile continues
+/// its trace and what is published later starts a new one
+pub struct TraceContext {
+    current: Mutex<raw::CriticalSectionRawMutex, Cell<Option<Trace>>>,
+}
+
+impl TraceContext {
+    pub(crate) const fn new() -> Self {
+        Self { current: Mutex::new(Cell::new(None)) }
+    }
+
+    /// Marks the event as the one being handled
+    pub(crate) fn enter(&self, trace: Trace) {
+        self.current.lock(|current| current.set(Some(trace)))
+    }
+
+    /// Ends the handling of the current event
+    pub(crate) fn leave(&self) {
+        self.current.lock(|current| current.set(None))
+    }
+
+    /// Marks the event as the one being handled until the guard is dropped
+    pub(crate) fn scope(&self, trace: Trace) -> TraceScope<'_> {
+        let previous = self.current.lock(|current| current.replace(Some(trace)));
+        TraceScope { context: self, previous }
+    }
+
+    /// Trace of an event published now
+    pub(crate) fn next(&self) -> Trace {
+        static SPAN: AtomicUsize = AtomicUsize::new(0);
+        let span = SPAN.fetch_add(1, Ordering::Relaxed);
+        match self.current.lock(Cell::get) {
+            Some(current) => Trace { id: current.id, span, parent: Some(current.span) },
+            None => Trace { id: span, span, parent: None },
+        }
+    }
+}
+
+/// Restores the trace handled before [`TraceContext::scope`]
+pub(crate) struct TraceScope<'a> {
+    context: &'a TraceContext,
+    previous: Option<Trace>,
+}
+
+impl Drop for TraceScope<'_> {
+    fn drop(&mut self) {
+        self.context.current.lock(|current| current.set(self.previous))
+    }
+}
+
 pub mod traits {
     use crate::service::traits::Service;
     use crate::traits::Notifier;
@@ -0,0 +1,224 @@
+//! Test harness for a single service, built with the `test-harness` feature.
+//!
+//! The notifier is a static, so the harness doesn't build one. It captures everything the service under test
+//! publishes on the current thread, publishes events in place of other services and answers RPC calls with
+//! scripted responses. The service under test has to run on the thread of the harness, and only one harness
+//! per notifier may be active at a time
+
+use crate::event::{self, traits as __evt};
+use crate::pubsub::{self, traits as __pub};
+use crate::rpc::{self, traits as __rpc};
+use crate::service::traits as __svc;
+use crate::traits as __traits;
+use core::any::Any;
+use core::cell::RefCell;
+use core::future::poll_fn;
+use core::marker::PhantomData;
+use core::task::{Poll, Waker};
+use embassy_time::{Duration, Timer};
+use std::boxed::Box;
+use std::vec::Vec;
+use varuemb_utils::select;
+
+struct Captured {
+    src: &'static crate::Metadata,
+    event: Box<dyn Any>,
+}
+
+#[derive(Default)]
+struct Capture {
+    events: Vec<Captured>,
+    waker: Option<Waker>,
+}
+
+std::thread_local! {
+    static CAPTURE: RefCell<Option<Capture>> = const { RefCell::new(None) };
+}
+
+pub(crate) fn capture<N, E>(event: &event::Event<N, E>)
+where
+    N: __traits::Notifier,
+    E: __evt::Event<N>,
+{
+    CAPTURE.with_borrow_mut(|capture| {
+        let Some(capture) = capture else {
+            return;
+        };
+        capture.events.push(Captured { src: event.meta.src, event: Box::new(event.clone()) });
+        if let Some(waker) = capture.waker.take() {
+            waker.wake()
+        }
+    })
+}
+
+/// Harness for the service `S` of the notifier `N`, capturing stops when it's dropped
+pub struct Harness<N, S> {
+    _phantom: PhantomData<*const (N, S)>,
+}
+
+impl<N, S> Harness<N, S>
+where
+    N: __traits::NotifierService<S>,
+    S: __svc::Service<N>,
+{
+    pub fn new() -> Self {
+        CAPTURE.set(Some(Capture::default()));
+        Self { _phantom: PhantomData }
+    }
+
+    /// Metadata of the instance `index` of the service `P`, to publish as it
+    pub fn metadata<P>(&self, index: usize) -> &'static crate::Metadata
+    where
+        N: __traits::NotifierService<P>,
+        P: __svc::Service<N, Impl: __pub::CanMetadata>,
+    {
+        <P::Impl as __pub::CanMetadata>::metadata(index)
+    }
+
+    /// Publishes the event as the first instance of the service it belongs to
+    pub fn publish<E>(&self, data: E) -> pubsub::PublishData
+    where
+        E: __evt::Event<N>,
+        N: __traits::NotifierService<E::Service>,
+        E::Service: __svc::Service<N, Impl: __pub::CanMetadata>,
+        [(); <E::Service as __svc::Service<N>>::COUNT]:,
+        pubsub::PubSub<crate::GetPubSub<N, E::Service>>: __pub::CanPublish<E, Notifier = N>,
+    {
+        self.publish_as(self.metadata::<E::Service>(0), data)
+    }
+
+    /// Publishes the event as the service instance `meta`, which has to be the one the event belongs to
+    pub fn publish_as<E>(&self, meta: &'static crate::Metadata, data: E) -> pubsub::PublishData
+    where
+        E: __evt::Event<N>,
+        N: __traits::NotifierService<E::Service>,
+        [(); <E::Service as __svc::Service<N>>::COUNT]:,
+        pubsub::PubSub<crate::GetPubSub<N, E::Service>>: __pub::CanPublish<E, Notifier = N>,
+    {
+        let service = <N as __traits::NotifierService<E::Service>>::ID;
+        assert_eq!(meta.id, service, "Event {:?} can't be published as {}", data, meta);
+        let container = <E::Service as __svc::Service<N>>::notif();
+        __pub::CanPublish::publish(__pub::GetPubSub::__get(container, meta.index.unwrap_or_default()), data)
+    }
+
+    /// Takes the oldest captured event `E` published by the service under test
+    pub fn try_take<E>(&self) -> Option<event::Event<N, E>>
+    where
+        E: __evt::Event<N>,
+    {
+        let service = <N as __traits::NotifierService<S>>::ID;
+        CAPTURE.with_borrow_mut(|capture| {
+            let events = &mut capture.as_mut()?.events;
+            let position = events
+                .iter()
+                .position(|captured| captured.src.id == service && captured.event.is::<event::Event<N, E>>())?;
+            events.remove(position).event.downcast().ok().map(|event| *event)
+        })
+    }
+
+    /// Takes all captured events `E` published by the service under test
+    pub fn take_all<E>(&self) -> Vec<event::Event<N, E>>
+    where
+        E: __evt::Event<N>,
+    {
+        core::iter::from_fn(|| self.try_take::<E>()).collect()
+    }
+
+    /// Drops the captured events
+    pub fn clear(&self) {
+        CAPTURE.with_borrow_mut(|capture| capture.iter_mut().for_each(|capture| capture.events.clear()))
+    }
+
+    /// Waits for the service under test to publish `E`, panics after `timeout`
+    pub async fn expect_published<E>(&self, timeout: Duration) -> event::Event<N, E>
+    where
+        E: __evt::Event<N>,
+    {
+        let published = poll_fn(|cx| match self.try_take::<E>() {
+            Some(event) => Poll::Ready(event),
+            None => {
+                CAPTURE.with_borrow_mut(|capture| {
+                    if let Some(capture) = capture {
+                        capture.waker = Some(cx.waker().clone())
+                    }
+                });
+                Poll::Pending
+            }
+        });
+        select! {
+            event = published => { event }
+            _timeout = Timer::after(timeout) => {
+                panic!("{} didn't publish {} in {}", N::NAME, core::any::type_name::<E>(), timeout)
+            }
+        }
+    }
+
+    /// Checks that the service under test doesn't publish `E` during `timeout`
+    pub async fn expect_not_published<E>(&self, timeout: Duration)
+    where
+        E: __evt::Event<N>,
+    {
+        Timer::after(timeout).await;
+        if let Some(event) = self.try_take::<E>() {
+            panic!("{} published unexpected {:?}", N::NAME, event)
+        }
+    }
+
+    /// Takes the next RPC call to the instance `index` of `R`, for the test to answer it as it needs
+    pub async fn request<R>(&self, index: usize) -> rpc::RpcRequest<R::Impl>
+    where
+        N: __traits::NotifierService<R>,
+        R: __rpc::RpcProvider<N> + 'static,
+        R::Impl: __rpc::Rpc<Notifier = N, Service = R> + __pub::CanMetadata,
+        [(); R::COUNT]:,
+        for<'r> &'r R::Request: Into<usize>,
+        pubsub::PubSub<R::Impl>: __pub::Subscribed<rpc::Request<R::Impl>, Notifier = N>,
+    {
+        let mut subscriber = __pub::GetPubSub::__get(R::notif(), index).subscriber::<rpc::Request<R::Impl>>();
+        R::rpc_request(&mut subscriber).await
+    }
+
+    /// Answers the next RPC call to the instance `index` of `R` with `script`, returns the request
+    pub async fn answer<R>(
+        &self,
+        index: usize,
+        script: impl FnOnce(&R::Request) -> Result<R::Response, R::Error>,
+    ) -> R::Request
+    where
+        N: __traits::NotifierService<R>,
+        R: __rpc::RpcProvider<N> + 'static,
+        R::Impl: __rpc::Rpc<Notifier = N, Service = R> + __pub::CanMetadata,
+        [(); R::COUNT]:,
+        for<'r> &'r R::Request: Into<usize>,
+        for<'r> &'r R::Response: Into<usize>,
+        pubsub::PubSub<R::Impl>:
+            __pub::Subscribed<rpc::Request<R::Impl>, Notifier = N> + __pub::CanPublish<rpc::Response<R::Impl>, Notifier = N>,
+    {
+        let mut request = self.request::<R>(index).await;
+        let data = request.take();
+        let res = match script(&data) {
+            Ok(response) => request.response(response).await,
+            Err(err) => request.response_err(err),
+        };
+        if let Err(err) = res {
+            panic!("Scripted response to {:?} is rejected: {:?}", data, err)
+        }
+        data
+    }
+}
+
+impl<N, S> Default for Harness<N, S>
+where
+    N: __traits::NotifierService<S>,
+    S: __svc::Service<N>,
+{
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<N, S> Drop for Harness<N, S> {
+    fn drop(&mut self) {
+        CAPTURE.set(None)
+    }
+}
@@ -0,0 +1,112 @@
+use crate::pubsub::{self, traits as __pub};
+use crate::rpc::traits as __rpc;
+use crate::service::{self, traits as __svc};
+use crate::traits as __traits;
+
+/// State of one subscription of a service instance
+#[derive(Debug, Clone, Copy)]
+pub struct Subscription {
+    pub event: &'static str,
+    pub len: usize,
+    pub capacity: usize,
+    pub receivers: usize,
+    pub sending: bool,
+    pub overflow: pubsub::Overflow,
+    pub dropped: usize,
+    pub failed: usize,
+}
+
+impl Subscription {
+    pub fn new<E: 'static>(event: &'static str, subscription: &'static dyn pubsub::DynSubscription<E>) -> Self {
+        let state = subscription.state();
+        Self {
+            event,
+            len: subscription.len(),
+            capacity: subscription.capacity(),
+            receivers: state.receivers(),
+            sending: state.is_sending(),
+            overflow: state.overflow(),
+            dropped: state.dropped(),
+            failed: state.failed(),
+        }
+    }
+}
+
+/// Events a service implementation publishes and subscribes to, implemented by `#[derive(Service)]`
+pub trait Introspect {
+    const PUBLISHES: &'static [&'static str];
+    const SUBSCRIBES: &'static [&'static str];
+
+    fn __subscriptions(&'static self, visitor: &mut dyn FnMut(Subscription));
+}
+
+/// Service of a notifier, as seen at runtime
+pub trait ServiceInfo {
+    fn id(&self) -> usize;
+    fn name(&self) -> &'static str;
+    fn count(&self) -> usize;
+    fn publishes(&self) -> &'static [&'static str];
+    fn subscribes(&self) -> &'static [&'static str];
+    fn metadata(&self, index: usize) -> &'static crate::Metadata;
+    /// Visits the subscriptions of the instance `index`
+    fn subscriptions(&'static self, index: usize, visitor: &mut dyn FnMut(Subscription));
+    /// Number of the RPC requests to the instance `index` cancelled by their callers
+    fn cancelled(&'static self, index: usize) -> usize;
+}
+
+impl<N, S> ServiceInfo for service::Service<N, S>
+where
+    N: __traits::NotifierService<S>,
+    S: __svc::Service<N, Impl: Introspect + __pub::CanMetadata>,
+    [(); S::COUNT]:,
+{
+    fn id(&self) -> usize {
+        N::ID
+    }
+
+    fn name(&self) -> &'static str {
+        N::NAME
+    }
+
+    fn count(&self) -> usize {
+        S::COUNT
+    }
+
+    fn publishes(&self) -> &'static [&'static str] {
+        S::Impl::PUBLISHES
+    }
+
+    fn subscribes(&self) -> &'static [&'static str] {
+        S::Impl::SUBSCRIBES
+    }
+
+    fn metadata(&self, index: usize) -> &'static crate::Metadata {
+        <S::Impl as __pub::CanMetadata>::metadata(index)
+    }
+
+    fn subscriptions(&'static self, index: usize, visitor: &mut dyn FnMut(Subscription)) {
+        if let Some(pubsub) = self.pubsub.inner.get(index) {
+            pubsub.inner.__subscriptions(visitor)
+        }
+    }
+
+    fn cancelled(&'static self, index: usize) -> usize {
+        let cancellation = self.pubsub.inner.get(index).and_then(|pubsub| __rpc::Cancellable::__cancellation(&pubsub.inner));
+        cancellation.map_or(0, |cancellation| cancellation.count())
+    }
+}
+
+/// Services of a notifier, implemented by `#[notifier]`
+pub trait Topology: __traits::Notifier {
+    fn services(&'static self, visitor: &mut dyn FnMut(&'static dyn ServiceInfo));
+
+    /// Visits every subscription of every service instance
+    fn subscriptions(&'static self, mut visitor: impl FnMut(&'static crate::Metadata, Subscription)) {
+        self.services(&mut |service| {
+            for index in 0..service.count() {
+                let meta = service.metadata(index);
+                service.subscriptions(index, &mut |subscription| visitor(meta, subscription));
+            }
+        })
+    }
+}
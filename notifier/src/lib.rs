@@ -1,4 +1,4 @@
-#![no_std]
+#![cfg_attr(not(feature = "std"), no_std)]
 //
 #![allow(async_fn_in_trait)]
 #![allow(incomplete_features)]
@@ -31,8 +31,12 @@ pub use varuemb_utils::{const_wrapper, select};
 
 pub mod calc;
 pub mod event;
+#[cfg(feature = "test-harness")]
+pub mod harness;
+pub mod introspect;
 pub mod metadata;
 pub mod pubsub;
+pub mod recorder;
 pub mod rpc;
 pub mod service;
 pub mod traits;
@@ -5,6 +5,7 @@ use crate::traits::*;
 use core::future::pending;
 use core::marker::PhantomData;
 use core::mem::ManuallyDrop;
+use core::task::{Context, Poll};
 
 pub trait Mixer<N: Notifier>: core::fmt::Debug {}
 
@@ -128,6 +129,13 @@ const _: () = {
             let event = data.subscriber.try_next()?;
             Some(event.map(data.mapper))
         }
+
+        pub fn poll_map(&mut self, cx: &mut Context<'_>) -> Poll<event::Event<N, M>> {
+            match P::Data::wrap_data(&mut self.data) {
+                Some(data) => data.subscriber.poll_next(cx).map(|event| event.map(data.mapper)),
+                None => Poll::Pending,
+            }
+        }
     }
 };
 
@@ -143,4 +151,5 @@ where
     fn __new_mixed(&'static self) -> Self::Mixed;
     async fn __mixed(mixed: &mut Self::Mixed) -> event::Event<Self::Notifier, M>;
     fn __try_mixed(mixed: &mut Self::Mixed) -> Option<event::Event<Self::Notifier, M>>;
+    fn __poll_mixed(mixed: &mut Self::Mixed, cx: &mut Context<'_>) -> Poll<event::Event<Self::Notifier, M>>;
 }
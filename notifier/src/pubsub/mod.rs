@@ -9,15 +9,20 @@ use core::ops::{Deref, Index};
 use core::sync::atomic::{AtomicUsize, Ordering};
 use varuemb_utils::assert::*;
 
-use embassy_sync::channel::TrySendError;
 use embassy_time::{Duration, Timer};
 use futures_util::FutureExt;
-pub use subscriber::{DynSubscription, MixedSubscriber, Subscriber, Subscription};
+pub use retained::Retained;
+pub use retry::{Backoff, Retry};
+pub use stream::EventStreamExt;
+pub use subscriber::{DynSubscription, Latest, MixedSubscriber, Overflow, State, Subscriber, Subscription};
 use varuemb_utils::select;
 
 pub(crate) use private::{PublishConfig, TargetState};
 
 pub mod mixer;
+mod retained;
+pub(crate) mod retry;
+pub mod stream;
 mod subscriber;
 pub mod traits;
 
@@ -65,6 +70,8 @@ mod private {
         pub(crate) error_handler: Err,
         pub(crate) inactive_is_err: bool,
         pub(crate) break_after_error: bool,
+        pub(crate) context: Option<&'static event::TraceContext>,
+        pub(crate) retry: Option<Retry>,
         pub(crate) _phantom: PhantomData<*const (N, ER)>,
     }
 }
@@ -75,6 +82,7 @@ pub enum Error<N, E, RE> {
     Timeout(event::Metadata, Duration),
     IncorrectResponse(&'static Metadata, usize),
     Response(&'static Metadata, usize, RE),
+    Cancelled(&'static Metadata, usize),
 }
 impl<N, E, RE> Error<N, E, RE> {
     pub fn into_response(self) -> Option<RE> {
@@ -99,6 +107,9 @@ impl<N, E: core::fmt::Debug, RE: core::fmt::Debug> core::fmt::Debug for Error<N,
                 .field("Id", &f1)
                 .field("Error", &err)
                 .finish(),
+            Error::Cancelled(f0, f1) => {
+                f.debug_struct("Cancelled").field("Meta", &format_args!("{}", f0)).field("Id", &f1).finish()
+            }
         }
     }
 }
@@ -174,6 +185,7 @@ where
 #[derive(Debug)]
 pub struct PublishData {
     pub id: usize,
+    pub trace: event::Trace,
     pub total: usize,
     pub errors: usize,
     pub published: usize,
@@ -181,8 +193,8 @@ pub struct PublishData {
 }
 
 impl PublishData {
-    fn new(id: usize, total: usize) -> Self {
-        Self { id, total, errors: 0, published: 0, not_published: 0 }
+    fn new(id: usize, trace: event::Trace, total: usize) -> Self {
+        Self { id, trace, total, errors: 0, published: 0, not_published: 0 }
     }
 }
 
@@ -204,6 +216,8 @@ where
     break_after_error: bool,
     selector: PublishSelector<I>,
     error_handler: Eh,
+    context: Option<&'static event::TraceContext>,
+    retry: Option<Retry>,
     _phantom: PhantomData<*const (E, ER)>,
 }
 
@@ -239,6 +253,8 @@ where
             inactive_is_err: self.inactive_is_err,
             break_after_error: self.break_after_error,
             selector: self.selector,
+            context: self.context,
+            retry: self.retry,
             _phantom: Default::default(),
         }
     }
@@ -254,6 +270,8 @@ where
             inactive_is_err: self.inactive_is_err,
             break_after_error: self.break_after_error,
             error_handler: self.error_handler,
+            context: self.context,
+            retry: self.retry,
             _phantom: self._phantom,
         }
     }
@@ -269,10 +287,26 @@ where
             inactive_is_err: self.inactive_is_err,
             break_after_error: self.break_after_error,
             error_handler: self.error_handler,
+            context: self.context,
+            retry: self.retry,
             _phantom: self._phantom,
         }
     }
 
+    /// Retries sending to a subscriber after transient errors. Only asynchronous publishing retries, it tries
+    /// the failed subscribers again after the backoff, once the others got the event. Synchronous publishing
+    /// ignores the policy and reports `Full` at once, as the subscribers can't drain while it runs
+    pub fn set_retry(mut self, retry: Retry) -> Self {
+        self.retry = Some(retry);
+        self
+    }
+
+    /// Continues the trace of another service instance instead of the publishing one
+    pub(crate) fn set_context(mut self, context: &'static event::TraceContext) -> Self {
+        self.context = Some(context);
+        self
+    }
+
     fn make_config(
         self,
         data: E,
@@ -281,7 +315,7 @@ where
     where
         E: __evt::Event<P::Notifier, Service = P::Service>,
     {
-        let Self { allow_inactive, selector, error_handler, inactive_is_err, break_after_error, .. } = self;
+        let Self { allow_inactive, selector, error_handler, inactive_is_err, break_after_error, context, retry, .. } = self;
         let allow_inactive = allow_inactive.unwrap_or(true);
         let checker = move |state: &subscriber::State, meta| -> TargetState {
             match &selector {
@@ -298,6 +332,8 @@ where
             inactive_is_err,
             break_after_error,
             error_handler,
+            context,
+            retry,
             _phantom: Default::default(),
         }
     }
@@ -332,11 +368,17 @@ pub struct PubSub<P: traits::PubSub> {
     pub(crate) inner: P,
     index: AtomicUsize,
     event_id: AtomicUsize,
+    pub(crate) context: event::TraceContext,
 }
 
 impl<P: traits::PubSub> PubSub<P> {
     pub const fn new(i: usize) -> Self {
-        Self { index: AtomicUsize::new(i), inner: P::NEW, event_id: AtomicUsize::new(0) }
+        Self {
+            index: AtomicUsize::new(i),
+            inner: P::NEW,
+            event_id: AtomicUsize::new(0),
+            context: event::TraceContext::new(),
+        }
     }
 }
 
@@ -358,7 +400,7 @@ where
         S: __svc::Service<N, Impl: __rpc::Rpc> + __rpc::RpcProvider<N>,
         P: traits::Subscribed<rpc::Response<S::Impl>, Notifier = N> + traits::CanMetadata,
     {
-        N::get().__get().rpc(traits::Subscribed::channel(self), self.metadata())
+        N::get().__get().rpc(traits::Subscribed::channel(self), self.metadata(), &self.context)
     }
 
     pub fn subscriber<E>(&'static self) -> Subscriber<P::Notifier, E>
@@ -382,7 +424,7 @@ where
         P: mixer::SubscriberMixer<M>,
         M: mixer::Mixer<P::Notifier>,
     {
-        MixedSubscriber::<P, M>::new(&self.inner)
+        MixedSubscriber::<P, M>::new(&self.inner, &self.context)
     }
 
     pub fn publisher<E>(&self) -> PublishConfigurator<P, impl Fn(Error<P::Notifier, E, ()>), E>
@@ -397,6 +439,8 @@ where
             break_after_error: false,
             selector: PublishSelector::None,
             error_handler: <Self as traits::CanPublish<E>>::error_handler,
+            context: None,
+            retry: None,
             _phantom: Default::default(),
         }
     }
@@ -433,13 +477,53 @@ where
         self.inner.channel()
     }
     fn subscriber(&'static self) -> self::subscriber::Subscriber<N, E> {
-        self.inner.subscriber()
+        let mut subscriber = self.inner.subscriber();
+        subscriber.context = Some(&self.context);
+        // Only the first receiver gets the retained events, the others share its queue. A first receiver
+        // subscribing again gets them again, see `Retained`
+        if subscriber.channel.state().receivers.load(Ordering::Acquire) == 1 {
+            <E as DeliverRetained<P, E>>::deliver(self, subscriber.channel);
+        }
+        subscriber
     }
     fn count(&'static self) -> usize {
         self.inner.count()
     }
 }
 
+/// Sends the events retained by the publishers of `E` to a new subscription of `P`
+trait DeliverRetained<P: traits::PubSub, E: __evt::Event<P::Notifier>> {
+    fn deliver(pubsub: &PubSub<P>, subscription: GetDynSubscription<P::Notifier, E>);
+}
+impl<P, E> DeliverRetained<P, E> for E
+where
+    P: traits::PubSub,
+    E: __evt::Event<P::Notifier>,
+{
+    default fn deliver(_: &PubSub<P>, _: GetDynSubscription<P::Notifier, E>) {}
+}
+impl<N, P, E> DeliverRetained<P, E> for E
+where
+    N: NotifierService<E::Service>,
+    P: traits::PubSub<Notifier = N> + traits::CanMetadata,
+    E: __evt::Event<N, Service: __svc::Service<N, Impl: traits::Publisher<E>>>,
+    [(); <E::Service as __svc::Service<N>>::COUNT]:,
+{
+    fn deliver(pubsub: &PubSub<P>, subscription: GetDynSubscription<N, E>) {
+        let dst = P::metadata(pubsub.index.load(Ordering::Relaxed));
+        for publisher in &<E::Service as __svc::Service<N>>::notif().inner {
+            let Some(mut event) = traits::Publisher::<E>::__retained(&publisher.inner).and_then(Retained::get) else {
+                continue;
+            };
+            event.meta.dst = dst;
+            event.print_publish();
+            if subscription.try_send(event).is_err() {
+                break;
+            }
+        }
+    }
+}
+
 impl<P, E> traits::CanPublish<E> for PubSub<P>
 where
     [(); P::Notifier::ID_COUNT]:,
@@ -470,16 +554,24 @@ where
     Eh: FnMut(Error<P::Notifier, E, ER>),
     Ch: for<'s> Fn(&'s subscriber::State, &'static Metadata) -> TargetState,
 {
-    let (mut event, event_id) = event::Event::new_pubsub(pub_sub, config.data.take().unwrap());
+    let context = config.context.unwrap_or(&pub_sub.context);
+    let (mut event, event_id) = event::Event::new_pubsub(pub_sub, config.data.take().unwrap(), context);
     event.print_pre_publish();
+    crate::recorder::publish(&event);
+    #[cfg(feature = "test-harness")]
+    crate::harness::capture(&event);
+    if let Some(retained) = traits::Publisher::<E>::__retained(&pub_sub.inner) {
+        retained.store(event.clone());
+    }
 
-    let mut data = PublishData::new(event_id, P::Notifier::CHANNEL_COUNT);
+    let mut data = PublishData::new(event_id, event.meta.trace, P::Notifier::CHANNEL_COUNT);
     let subscribers = crate::subscribers().map(|item| {
         let meta = item.meta();
         let state = item.subscriber.state();
         match (config.checker)(&state, meta) {
             TargetState::Inactive if config.inactive_is_err => {
                 data.errors += 1;
+                crate::recorder::delivery(&event.meta, meta, crate::recorder::Outcome::Inactive);
                 (config.error_handler)(Error::Inactive(event.meta));
                 return Err(config.break_after_error);
             }
@@ -489,9 +581,13 @@ where
             }
             TargetState::Ok => (),
         }
+        event.meta.dst = meta;
+        if !item.subscriber.accepts(&event) {
+            data.not_published += 1;
+            return Err(false);
+        }
         state.sending.store(true, Ordering::Release);
 
-        event.meta.dst = meta;
         let event = event.clone();
         event.print_publish();
         Ok((item.subscriber, event))
@@ -521,6 +617,7 @@ where
         Ok(_) => {
             let is_ok = !matches!((config.checker)(subscriber.state(), meta), TargetState::Ok);
             if is_ok {
+                crate::recorder::delivery(&meta_evt, meta, crate::recorder::Outcome::Inactive);
                 subscriber.clear();
                 if config.inactive_is_err {
                     data.errors += 1;
@@ -530,12 +627,15 @@ where
                     data.not_published += 1;
                 }
             } else {
+                crate::recorder::delivery(&meta_evt, meta, crate::recorder::Outcome::Delivered);
                 data.published += 1;
             }
         }
         Err(err) => {
+            crate::recorder::delivery(&meta_evt, meta, crate::recorder::Outcome::Failed);
             error = true;
             data.errors += 1;
+            subscriber.state().failed.fetch_add(1, Ordering::Relaxed);
             (config.error_handler)(err)
         }
     }
@@ -547,6 +647,61 @@ where
     false
 }
 
+/// Sends the event without waiting, making room for it as the overflow policy of the subscription says
+fn try_send<N, E, ER>(subscriber: GetDynSubscription<N, E>, mut event: GetEvent<N, E>) -> Result<(), Error<N, E, ER>>
+where
+    N: Notifier,
+    E: __evt::Event<N>,
+{
+    let state = subscriber.state();
+    loop {
+        event = match subscriber.try_send(event) {
+            Ok(()) => return Ok(()),
+            Err(event) => event,
+        };
+        match state.overflow {
+            Overflow::Error => return Err(Error::Full(event)),
+            Overflow::DropNewest => {
+                state.dropped.fetch_add(1, Ordering::Relaxed);
+                return Ok(());
+            }
+            // A `Latest` subscription is never full, it overwrites the event itself
+            Overflow::DropOldest | Overflow::Latest => {
+                if subscriber.try_receive().is_some() {
+                    state.dropped.fetch_add(1, Ordering::Relaxed);
+                }
+            }
+        }
+    }
+}
+
+/// Sends the event within `timeout`. With a `retry` that retries `Full` a full subscription fails at once,
+/// so the policy decides when to try again
+async fn send_with<N, E, ER>(
+    subscriber: GetDynSubscription<N, E>,
+    event: GetEvent<N, E>,
+    timeout: Option<Duration>,
+    retry: Option<Retry>,
+) -> Result<(), Error<N, E, ER>>
+where
+    N: Notifier,
+    E: __evt::Event<N>,
+{
+    if !matches!(subscriber.state().overflow, Overflow::Error) || retry.is_some_and(|retry| retry.retries_full()) {
+        return try_send(subscriber, event);
+    }
+    let meta = event.meta;
+    let timer = if let Some(timeout) = timeout {
+        Timer::after(timeout).map(move |_| timeout).left_future()
+    } else {
+        pending().right_future()
+    };
+    select! {
+        _send = subscriber.send(event) => { Ok(()) }
+        timeout = timer => { Err(Error::Timeout(meta, timeout)) }
+    }
+}
+
 impl<P, E> traits::CanPublishRaw<E> for PubSub<P>
 where
     [(); P::Notifier::ID_COUNT]:,
@@ -573,7 +728,8 @@ where
 
             let meta = event.meta.dst;
             let meta_evt = event.meta;
-            let res = subscriber.sender().try_send(event).map_err(|TrySendError::Full(evt)| Error::Full(evt));
+            // The subscriber can't drain during a synchronous publish, so the retry policy is not applied
+            let res = try_send(subscriber, event);
 
             if post_publish(res, &mut config, subscriber, meta, meta_evt, &mut data) {
                 break;
@@ -589,8 +745,14 @@ where
         Ch: for<'s> Fn(&'s subscriber::State, &'static Metadata) -> TargetState,
     {
         let (subscribers, mut data) = pre_publish(self, &mut config);
-
-        for item in subscribers {
+        let retry = config.retry;
+        // Targets to send to again, with the error of their last attempt, once every target got the first one
+        let mut failed: [Option<(GetDynSubscription<P::Notifier, E>, GetEvent<P::Notifier, E>, Error<P::Notifier, E, ER>)>;
+            P::Notifier::CHANNEL_COUNT] = core::array::from_fn(|_| None);
+        let mut delay = None;
+        let mut stop = false;
+
+        for (item, failed) in subscribers.into_iter().zip(failed.iter_mut()) {
             let (subscriber, event) = match item {
                 Ok(event) => event,
                 Err(true) => break,
@@ -599,20 +761,59 @@ where
 
             let meta = event.meta.dst;
             let meta_evt = event.meta;
-            let timer = if let Some(timeout) = config.timeout {
-                Timer::after(timeout).map(move |_| timeout).left_future()
-            } else {
-                pending().right_future()
+            let retry_event = retry.map(|_| event.clone());
+            let res = send_with(subscriber, event, config.timeout, retry).await;
+            let res = match (res, retry, retry_event) {
+                (Err(err), Some(retry), Some(event)) => match retry.next(1, &err) {
+                    Some(next) => {
+                        meta_evt.print_publish_retry(retry::kind(&err), 1, retry.attempts(), next);
+                        *failed = Some((subscriber, event, err));
+                        delay = Some(next);
+                        continue;
+                    }
+                    None => Err(err),
+                },
+                (res, ..) => res,
             };
-            let res = select! {
-                _send = subscriber.sender().send(event) => { Ok(()) }
-                timeout = timer => { Err(Error::Timeout(meta_evt, timeout)) }
-            };
-
             if post_publish(res, &mut config, subscriber, meta, meta_evt, &mut data) {
+                stop = true;
                 break;
             }
         }
+
+        let mut attempt = 1;
+        while let (Some(retry), Some(wait), false) = (retry, delay.take(), stop) {
+            Timer::after(wait).await;
+            attempt += 1;
+            for failed in failed.iter_mut() {
+                let Some((subscriber, event, _)) = failed.take() else {
+                    continue;
+                };
+                let meta = event.meta.dst;
+                let meta_evt = event.meta;
+                let retry_event = event.clone();
+                let res = match send_with(subscriber, event, config.timeout, Some(retry)).await {
+                    Err(err) => match retry.next(attempt, &err) {
+                        Some(next) => {
+                            meta_evt.print_publish_retry(retry::kind(&err), attempt, retry.attempts(), next);
+                            *failed = Some((subscriber, retry_event, err));
+                            delay = Some(next);
+                            continue;
+                        }
+                        None => Err(err),
+                    },
+                    res => res,
+                };
+                if post_publish(res, &mut config, subscriber, meta, meta_evt, &mut data) {
+                    stop = true;
+                    break;
+                }
+            }
+        }
+        // Publishing stopped after an error, the targets still waiting for an attempt keep their last error
+        for (subscriber, event, err) in failed.into_iter().flatten() {
+            post_publish(Err(err), &mut config, subscriber, event.meta.dst, event.meta, &mut data);
+        }
         data
     }
 }
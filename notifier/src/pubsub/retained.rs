@@ -0,0 +1,35 @@
+use super::{__evt, event, traits};
+use core::cell::RefCell;
+use embassy_sync::blocking_mutex::{raw, Mutex};
+
+type RawMutex = raw::CriticalSectionRawMutex;
+
+/// Last published event, kept by a publisher for subscribers that come later.
+///
+/// A subscription gets the retained events whenever its first receiver subscribes, so also again after all
+/// its receivers were dropped. The subscription doesn't record what it got before: its queue is cleared when
+/// the last receiver leaves, and the retained events are how the next one learns the current state
+pub struct Retained<P, E>
+where
+    P: traits::PubSub,
+{
+    inner: Mutex<RawMutex, RefCell<Option<event::Event<P::Notifier, E>>>>,
+}
+
+impl<P, E> Retained<P, E>
+where
+    P: traits::PubSub,
+    E: __evt::Event<P::Notifier>,
+{
+    pub const fn default() -> Self {
+        Self { inner: Mutex::new(RefCell::new(None)) }
+    }
+
+    pub(crate) fn store(&self, event: event::Event<P::Notifier, E>) {
+        self.inner.lock(|retained| *retained.borrow_mut() = Some(event))
+    }
+
+    pub(crate) fn get(&self) -> Option<event::Event<P::Notifier, E>> {
+        self.inner.lock(|retained| retained.borrow().clone())
+    }
+}
@@ -0,0 +1,106 @@
+//! Retry policy for the transient errors of publishing and RPC calls
+
+use super::Error;
+use embassy_time::Duration;
+
+/// Delay before the next attempt
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Backoff {
+    Fixed(Duration),
+    /// Starts with `initial` and doubles after each attempt, up to `max`
+    Exponential {
+        initial: Duration,
+        max: Duration,
+    },
+}
+
+impl Backoff {
+    fn delay(self, attempt: u32) -> Duration {
+        match self {
+            Backoff::Fixed(delay) => delay,
+            Backoff::Exponential { initial, max } => {
+                let ticks = initial.as_ticks().saturating_mul(1u64 << attempt.saturating_sub(1).min(63));
+                Duration::from_ticks(ticks).min(max)
+            }
+        }
+    }
+}
+
+/// How many times and after which errors a publish or a call is attempted again.
+///
+/// Retries `Full` and `Timeout` by default, `Inactive` is retried only by RPC calls as publishing skips
+/// inactive subscribers before sending. An RPC call retries only sending the request, a request that reached
+/// the provider is never sent again, whatever happens to its response
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Retry {
+    attempts: u32,
+    backoff: Backoff,
+    full: bool,
+    timeout: bool,
+    inactive: bool,
+}
+
+impl Retry {
+    /// `attempts` counts the first one too
+    pub const fn new(attempts: u32, backoff: Backoff) -> Self {
+        Self { attempts, backoff, full: true, timeout: true, inactive: false }
+    }
+
+    pub const fn fixed(attempts: u32, delay: Duration) -> Self {
+        Self::new(attempts, Backoff::Fixed(delay))
+    }
+
+    pub const fn exponential(attempts: u32, initial: Duration, max: Duration) -> Self {
+        Self::new(attempts, Backoff::Exponential { initial, max })
+    }
+
+    pub const fn on_full(mut self, retry: bool) -> Self {
+        self.full = retry;
+        self
+    }
+
+    pub const fn on_timeout(mut self, retry: bool) -> Self {
+        self.timeout = retry;
+        self
+    }
+
+    pub const fn on_inactive(mut self, retry: bool) -> Self {
+        self.inactive = retry;
+        self
+    }
+
+    pub const fn attempts(&self) -> u32 {
+        self.attempts
+    }
+
+    pub const fn backoff(&self) -> Backoff {
+        self.backoff
+    }
+
+    pub(crate) const fn retries_full(&self) -> bool {
+        self.full
+    }
+
+    /// Delay before the next attempt if `err` after the `attempt`-th one is retried
+    pub(crate) fn next<N, E, RE>(&self, attempt: u32, err: &Error<N, E, RE>) -> Option<Duration> {
+        let retried = match err {
+            Error::Full(_) => self.full,
+            Error::Timeout(..) => self.timeout,
+            Error::Inactive(_) => self.inactive,
+            Error::IncorrectResponse(..) | Error::Response(..) | Error::Cancelled(..) => false,
+        };
+        (retried && attempt < self.attempts).then(|| self.backoff.delay(attempt))
+    }
+}
+
+/// Name of the error kind, for the retry logs
+pub(crate) fn kind<N, E, RE>(err: &Error<N, E, RE>) -> &'static str {
+    match err {
+        Error::Full(_) => "full",
+        Error::Inactive(_) => "inactive",
+        Error::Timeout(..) => "timeout",
+        Error::IncorrectResponse(..) => "incorrect response",
+        Error::Response(..) => "response",
+        Error::Cancelled(..) => "cancelled",
+    }
+}
@@ -0,0 +1,175 @@
+//! Adapters for streams of notifier events, such as [`super::Subscriber`] and [`super::MixedSubscriber`].
+//!
+//! The adapters keep at most one event and never allocate. An adapter that skips events marks the next
+//! event it yields as [`event::Event::dropped`]. [`Debounce`] and [`Coalesce`] yield the event they keep
+//! when the inner stream ends and are fused, they don't poll it again
+
+use crate::event;
+use core::future::Future;
+use core::pin::Pin;
+use core::task::{ready, Context, Poll};
+use embassy_time::{Duration, Instant, Timer};
+use futures_util::Stream;
+
+pub trait EventStreamExt<N, E>: Stream<Item = event::Event<N, E>> + Unpin + Sized {
+    /// Keeps the events published by `src`, a metadata without index matches all instances of the service
+    fn filter_src(self, src: &'static crate::Metadata) -> FilterSrc<Self> {
+        FilterSrc { inner: self, src }
+    }
+
+    /// Yields the latest event once no other event arrives for `quiet`
+    fn debounce(self, quiet: Duration) -> Debounce<Self> {
+        Debounce { inner: self, quiet, pending: None, timer: None, done: false }
+    }
+
+    /// Yields an event and skips the following ones for `period`
+    fn throttle(self, period: Duration) -> Throttle<Self> {
+        Throttle { inner: self, period, until: None, skipped: false }
+    }
+
+    /// Yields only the latest of the events ready at once
+    fn coalesce(self) -> Coalesce<Self> {
+        Coalesce { inner: self, done: false }
+    }
+}
+
+impl<N, E, S> EventStreamExt<N, E> for S where S: Stream<Item = event::Event<N, E>> + Unpin {}
+
+pub struct FilterSrc<S> {
+    inner: S,
+    src: &'static crate::Metadata,
+}
+
+impl<N, E, S> Stream for FilterSrc<S>
+where
+    S: Stream<Item = event::Event<N, E>> + Unpin,
+{
+    type Item = event::Event<N, E>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = self.get_mut();
+        loop {
+            match ready!(Pin::new(&mut this.inner).poll_next(cx)) {
+                Some(event) if *this.src != *event.meta.src => continue,
+                event => return Poll::Ready(event),
+            }
+        }
+    }
+}
+
+pub struct Debounce<S: Stream> {
+    inner: S,
+    quiet: Duration,
+    pending: Option<S::Item>,
+    timer: Option<Timer>,
+    done: bool,
+}
+
+// The pending event is never pinned
+impl<S: Stream + Unpin> Unpin for Debounce<S> {}
+
+impl<N, E, S> Stream for Debounce<S>
+where
+    S: Stream<Item = event::Event<N, E>> + Unpin,
+{
+    type Item = event::Event<N, E>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = self.get_mut();
+        // Fused, the inner stream isn't polled after its end
+        if this.done {
+            return Poll::Ready(None);
+        }
+        loop {
+            match Pin::new(&mut this.inner).poll_next(cx) {
+                Poll::Ready(Some(mut event)) => {
+                    event.meta.dropped |= this.pending.is_some();
+                    this.pending = Some(event);
+                    this.timer = Some(Timer::after(this.quiet));
+                }
+                // Flushes the pending event before the end
+                Poll::Ready(None) => {
+                    this.timer = None;
+                    this.done = true;
+                    return Poll::Ready(this.pending.take());
+                }
+                Poll::Pending => break,
+            }
+        }
+        let Some(timer) = this.timer.as_mut() else {
+            return Poll::Pending;
+        };
+        ready!(Pin::new(timer).poll(cx));
+        this.timer = None;
+        match this.pending.take() {
+            Some(event) => Poll::Ready(Some(event)),
+            None => Poll::Pending,
+        }
+    }
+}
+
+pub struct Throttle<S> {
+    inner: S,
+    period: Duration,
+    until: Option<Instant>,
+    skipped: bool,
+}
+
+impl<N, E, S> Stream for Throttle<S>
+where
+    S: Stream<Item = event::Event<N, E>> + Unpin,
+{
+    type Item = event::Event<N, E>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = self.get_mut();
+        loop {
+            let Some(mut event) = ready!(Pin::new(&mut this.inner).poll_next(cx)) else {
+                return Poll::Ready(None);
+            };
+            let now = Instant::now();
+            if this.until.is_some_and(|until| now < until) {
+                this.skipped = true;
+                continue;
+            }
+            event.meta.dropped |= core::mem::take(&mut this.skipped);
+            this.until = Some(now + this.period);
+            return Poll::Ready(Some(event));
+        }
+    }
+}
+
+pub struct Coalesce<S> {
+    inner: S,
+    done: bool,
+}
+
+impl<N, E, S> Stream for Coalesce<S>
+where
+    S: Stream<Item = event::Event<N, E>> + Unpin,
+{
+    type Item = event::Event<N, E>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = self.get_mut();
+        // Fused, the inner stream isn't polled after its end
+        if this.done {
+            return Poll::Ready(None);
+        }
+        let mut latest: Option<Self::Item> = None;
+        loop {
+            match Pin::new(&mut this.inner).poll_next(cx) {
+                Poll::Ready(Some(mut event)) => {
+                    event.meta.dropped |= latest.is_some();
+                    latest = Some(event);
+                }
+                // Yields the latest event before the end
+                Poll::Ready(None) => {
+                    this.done = true;
+                    return Poll::Ready(latest);
+                }
+                Poll::Pending => return latest.map_or(Poll::Pending, |event| Poll::Ready(Some(event))),
+            }
+        }
+    }
+}
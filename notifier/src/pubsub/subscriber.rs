@@ -1,15 +1,78 @@
 use super::{__evt, event, mixer, traits};
-use core::future::pending;
+use core::cell::RefCell;
+use core::future::{pending, poll_fn};
+use core::pin::Pin;
 use core::sync::atomic::Ordering::*;
-use core::sync::atomic::{AtomicBool, AtomicUsize};
-use embassy_sync::blocking_mutex::raw;
+use core::sync::atomic::{AtomicBool, AtomicPtr, AtomicUsize};
+use core::task::{Context, Poll};
+use embassy_sync::blocking_mutex::{raw, Mutex};
 use embassy_sync::channel;
+use embassy_sync::waitqueue::WakerRegistration;
+use futures_util::Stream;
 
 type RawMutex = raw::CriticalSectionRawMutex;
 
+/// Filter of a subscription, evaluated by the publisher before the event is queued
+pub type Filter<E> = fn(&E, &event::Metadata) -> bool;
+
+/// What publishing does when the queue of a subscription is full
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Overflow {
+    /// Drops the oldest queued event to make room for the new one
+    DropOldest,
+    /// Drops the new event
+    DropNewest,
+    /// Fails with [`super::Error::Full`], or waits for room when publishing with a timeout
+    Error,
+    /// Overwrites the event not received yet, the policy of a [`Latest`] subscription
+    Latest,
+}
+
 pub struct State {
     pub(crate) receivers: AtomicUsize,
     pub(crate) sending: AtomicBool,
+    pub(crate) overflow: Overflow,
+    pub(crate) dropped: AtomicUsize,
+    pub(crate) failed: AtomicUsize,
+    /// Erased [`Filter`] of the subscription event, null if there's none
+    pub(crate) filter: AtomicPtr<()>,
+}
+
+impl State {
+    const fn new(overflow: Overflow) -> Self {
+        Self {
+            receivers: AtomicUsize::new(0),
+            sending: AtomicBool::new(false),
+            overflow,
+            dropped: AtomicUsize::new(0),
+            failed: AtomicUsize::new(0),
+            filter: AtomicPtr::new(core::ptr::null_mut()),
+        }
+    }
+
+    /// Number of active receivers
+    pub fn receivers(&self) -> usize {
+        self.receivers.load(Relaxed)
+    }
+
+    /// Whether an event is being published to the subscription right now
+    pub fn is_sending(&self) -> bool {
+        self.sending.load(Relaxed)
+    }
+
+    pub fn overflow(&self) -> Overflow {
+        self.overflow
+    }
+
+    /// Number of events dropped by the overflow policy so far
+    pub fn dropped(&self) -> usize {
+        self.dropped.load(Relaxed)
+    }
+
+    /// Number of events that failed to be published to the subscription
+    pub fn failed(&self) -> usize {
+        self.failed.load(Relaxed)
+    }
 }
 
 pub struct Subscription<P, E, const C: usize>
@@ -28,7 +91,11 @@ where
     varuemb_utils::assert::Msg<{ super::assert::subscriber::<P, E>() }>: varuemb_utils::assert::IsTrue,
 {
     pub const fn default() -> Self {
-        Self::new()
+        Self::new(Overflow::Error)
+    }
+
+    pub const fn with_overflow(overflow: Overflow) -> Self {
+        Self::new(overflow)
     }
 }
 
@@ -37,11 +104,8 @@ where
     P: traits::PubSub,
     E: __evt::Event<P::Notifier>,
 {
-    pub(crate) const fn new() -> Self {
-        Self {
-            inner: channel::Channel::new(),
-            state: State { receivers: AtomicUsize::new(0), sending: AtomicBool::new(false) },
-        }
+    pub(crate) const fn new(overflow: Overflow) -> Self {
+        Self { inner: channel::Channel::new(), state: State::new(overflow) }
     }
 
     pub(crate) fn as_dyn(&'static self) -> &'static dyn DynSubscription<event::Event<P::Notifier, E>> {
@@ -61,11 +125,61 @@ where
 }
 
 pub trait DynSubscription<E: 'static> {
-    fn sender(&'static self) -> channel::DynamicSender<'static, E>;
-    fn receiver(&'static self) -> channel::DynamicReceiver<'static, E>;
+    fn try_send(&'static self, event: E) -> Result<(), E>;
+    fn poll_ready_to_send(&'static self, cx: &mut Context<'_>) -> Poll<()>;
+    fn try_receive(&'static self) -> Option<E>;
+    fn poll_receive(&'static self, cx: &mut Context<'_>) -> Poll<E>;
+    fn len(&'static self) -> usize;
+    fn capacity(&'static self) -> usize;
     fn state(&'static self) -> &'static State;
+    fn is_empty(&'static self) -> bool {
+        self.len() == 0
+    }
     fn clear(&'static self) {
-        while self.receiver().try_receive().is_ok() {}
+        while self.try_receive().is_some() {}
+    }
+}
+
+impl<E: 'static> dyn DynSubscription<E> {
+    pub async fn send(&'static self, event: E) {
+        let mut event = Some(event);
+        poll_fn(|cx| loop {
+            match self.try_send(event.take().unwrap()) {
+                Ok(()) => return Poll::Ready(()),
+                Err(back) => event = Some(back),
+            }
+            if self.poll_ready_to_send(cx).is_pending() {
+                return Poll::Pending;
+            }
+        })
+        .await
+    }
+
+    pub async fn receive(&'static self) -> E {
+        poll_fn(|cx| self.poll_receive(cx)).await
+    }
+}
+
+impl<N: 'static, E: 'static> dyn DynSubscription<event::Event<N, E>> {
+    /// Sets the filter unless the subscription has one already
+    pub(crate) fn set_filter(&'static self, filter: Filter<E>) -> bool {
+        let filter = filter as *mut ();
+        self.state().filter.compare_exchange(core::ptr::null_mut(), filter, AcqRel, Acquire).is_ok()
+    }
+
+    pub(crate) fn clear_filter(&'static self) {
+        self.state().filter.store(core::ptr::null_mut(), Release)
+    }
+
+    /// Whether the filter of the subscription accepts the event
+    pub(crate) fn accepts(&'static self, event: &event::Event<N, E>) -> bool {
+        let filter = self.state().filter.load(Acquire);
+        if filter.is_null() {
+            return true;
+        }
+        // Only `set_filter` of the same subscription stores it, so it's a `Filter<E>`
+        let filter = unsafe { core::mem::transmute::<*mut (), Filter<E>>(filter) };
+        filter(&event.data, &event.meta)
     }
 }
 
@@ -74,12 +188,133 @@ where
     P: traits::PubSub,
     E: __evt::Event<P::Notifier>,
 {
-    fn sender(&'static self) -> channel::DynamicSender<'static, event::Event<P::Notifier, E>> {
-        self.inner.sender().into()
+    fn try_send(&'static self, event: event::Event<P::Notifier, E>) -> Result<(), event::Event<P::Notifier, E>> {
+        self.inner.try_send(event).map_err(|channel::TrySendError::Full(event)| event)
+    }
+
+    fn poll_ready_to_send(&'static self, cx: &mut Context<'_>) -> Poll<()> {
+        self.inner.poll_ready_to_send(cx)
+    }
+
+    fn try_receive(&'static self) -> Option<event::Event<P::Notifier, E>> {
+        self.inner.try_receive().ok()
+    }
+
+    fn poll_receive(&'static self, cx: &mut Context<'_>) -> Poll<event::Event<P::Notifier, E>> {
+        self.inner.poll_receive(cx)
+    }
+
+    fn len(&'static self) -> usize {
+        self.inner.len()
+    }
+
+    fn capacity(&'static self) -> usize {
+        C
+    }
+
+    fn state(&'static self) -> &'static State {
+        &self.state
+    }
+}
+
+struct Slot<T> {
+    event: Option<T>,
+    waker: WakerRegistration,
+}
+
+/// Subscription keeping only the latest event, for state-like events.
+///
+/// Publishing never fails, a newer event overwrites the one not received yet and is marked as [`event::Event::dropped`]
+pub struct Latest<P, E>
+where
+    P: traits::PubSub,
+    E: __evt::Event<P::Notifier>,
+{
+    inner: Mutex<RawMutex, RefCell<Slot<event::Event<P::Notifier, E>>>>,
+    state: State,
+}
+
+impl<P, E> Latest<P, E>
+where
+    P: traits::PubSub,
+    E: traits::IsPublisher<P>,
+    varuemb_utils::assert::Msg<{ super::assert::subscriber::<P, E>() }>: varuemb_utils::assert::IsTrue,
+{
+    pub const fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<P, E> Latest<P, E>
+where
+    P: traits::PubSub,
+    E: __evt::Event<P::Notifier>,
+{
+    pub(crate) const fn new() -> Self {
+        let slot = Slot { event: None, waker: WakerRegistration::new() };
+        Self { inner: Mutex::new(RefCell::new(slot)), state: State::new(Overflow::Latest) }
     }
 
-    fn receiver(&'static self) -> channel::DynamicReceiver<'static, event::Event<P::Notifier, E>> {
-        self.inner.receiver().into()
+    pub(crate) fn as_dyn(&'static self) -> &'static dyn DynSubscription<event::Event<P::Notifier, E>> {
+        self
+    }
+}
+
+impl<P, E> From<&'static Latest<P, E>> for GetSubscriberRet<P::Notifier, E>
+where
+    P: traits::PubSub,
+    E: __evt::Event<P::Notifier>,
+{
+    fn from(ch: &'static Latest<P, E>) -> Self {
+        ch.as_dyn()
+    }
+}
+
+impl<P, E> DynSubscription<event::Event<P::Notifier, E>> for Latest<P, E>
+where
+    P: traits::PubSub,
+    E: __evt::Event<P::Notifier>,
+{
+    fn try_send(&'static self, mut event: event::Event<P::Notifier, E>) -> Result<(), event::Event<P::Notifier, E>> {
+        self.inner.lock(|slot| {
+            let mut slot = slot.borrow_mut();
+            event.meta.dropped = slot.event.is_some();
+            if event.meta.dropped {
+                self.state.dropped.fetch_add(1, Relaxed);
+            }
+            slot.event = Some(event);
+            slot.waker.wake();
+        });
+        Ok(())
+    }
+
+    fn poll_ready_to_send(&'static self, _: &mut Context<'_>) -> Poll<()> {
+        Poll::Ready(())
+    }
+
+    fn try_receive(&'static self) -> Option<event::Event<P::Notifier, E>> {
+        self.inner.lock(|slot| slot.borrow_mut().event.take())
+    }
+
+    fn poll_receive(&'static self, cx: &mut Context<'_>) -> Poll<event::Event<P::Notifier, E>> {
+        self.inner.lock(|slot| {
+            let mut slot = slot.borrow_mut();
+            match slot.event.take() {
+                Some(event) => Poll::Ready(event),
+                None => {
+                    slot.waker.register(cx.waker());
+                    Poll::Pending
+                }
+            }
+        })
+    }
+
+    fn len(&'static self) -> usize {
+        self.inner.lock(|slot| slot.borrow().event.is_some() as usize)
+    }
+
+    fn capacity(&'static self) -> usize {
+        1
     }
 
     fn state(&'static self) -> &'static State {
@@ -94,6 +329,11 @@ where
 {
     pub(crate) state: bool,
     pub(crate) channel: &'static dyn DynSubscription<event::Event<N, E>>,
+    /// Trace context of the receiving service instance, entered on every received event and left
+    /// when polled again
+    pub(crate) context: Option<&'static event::TraceContext>,
+    /// Whether the filter of the subscription was set by this subscriber
+    filtered: bool,
 }
 
 impl<N, E> Subscriber<N, E>
@@ -103,21 +343,83 @@ where
 {
     pub(crate) fn new(channel: &'static dyn DynSubscription<event::Event<N, E>>) -> Self {
         channel.state().receivers.fetch_add(1, AcqRel);
-        Self { channel, state: true }
+        Self { channel, state: true, context: None, filtered: false }
+    }
+
+    fn enter(&self, event: &event::Event<N, E>) {
+        if let Some(context) = self.context {
+            context.enter(event.meta.trace)
+        }
+    }
+
+    fn leave(&self) {
+        if let Some(context) = self.context {
+            context.leave()
+        }
     }
 
     pub fn try_next(&mut self) -> Option<event::Event<N, E>> {
         if !self.state {
             return None;
         }
-        self.channel.receiver().try_receive().ok()
+        self.leave();
+        let event = self.channel.try_receive()?;
+        self.enter(&event);
+        Some(event)
     }
 
     pub async fn next(&mut self) -> event::Event<N, E> {
         if !self.state {
             return pending().await;
         }
-        self.channel.receiver().receive().await
+        self.leave();
+        let event = self.channel.receive().await;
+        self.enter(&event);
+        event
+    }
+
+    pub fn poll_next(&mut self, cx: &mut Context<'_>) -> Poll<event::Event<N, E>> {
+        if !self.state {
+            return Poll::Pending;
+        }
+        self.leave();
+        let event = core::task::ready!(self.channel.poll_receive(cx));
+        self.enter(&event);
+        Poll::Ready(event)
+    }
+
+    /// Registers the filter in the subscription, the publisher doesn't queue the events it rejects.
+    /// See [`Subscriber::set_filter`], the subscriber is given back if the filter is rejected
+    pub fn with_filter(mut self, filter: Filter<E>) -> Result<Self, Self> {
+        match self.set_filter(Some(filter)) {
+            Ok(()) => Ok(self),
+            Err(_) => Err(self),
+        }
+    }
+
+    /// Replaces or removes the filter set by this subscriber, which is removed when it is dropped.
+    ///
+    /// The subscription queue is shared, so a filter is rejected and given back while other subscribers
+    /// of the subscription exist or one of them has set a filter. Subscribers made later, clones included,
+    /// receive the filtered events too
+    pub fn set_filter(&mut self, filter: Option<Filter<E>>) -> Result<(), Filter<E>> {
+        let Some(filter) = filter else {
+            if core::mem::take(&mut self.filtered) {
+                self.channel.clear_filter();
+            }
+            return Ok(());
+        };
+        if self.filtered {
+            self.channel.clear_filter();
+        } else if self.channel.state().receivers() > usize::from(self.state) {
+            return Err(filter);
+        }
+        self.filtered = self.channel.set_filter(filter);
+        if self.filtered {
+            Ok(())
+        } else {
+            Err(filter)
+        }
     }
 
     pub fn try_next_raw(&mut self) -> Option<E> {
@@ -132,6 +434,11 @@ where
         self.state
     }
 
+    /// Number of events dropped by the overflow policy of the subscription
+    pub fn dropped(&self) -> usize {
+        self.channel.state().dropped()
+    }
+
     pub fn set_state(&mut self, state: bool) {
         if state != self.state {
             if state {
@@ -165,13 +472,28 @@ where
     }
 }
 
+impl<N, E> Stream for Subscriber<N, E>
+where
+    E: __evt::Event<N>,
+    N: crate::traits::Notifier,
+{
+    type Item = event::Event<N, E>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        Subscriber::poll_next(self.get_mut(), cx).map(Some)
+    }
+}
+
 impl<N, E> Drop for Subscriber<N, E>
 where
     E: __evt::Event<N>,
     N: crate::traits::Notifier,
 {
     fn drop(&mut self) {
-        self.__drop()
+        self.__drop();
+        if self.filtered {
+            self.channel.clear_filter()
+        }
     }
 }
 
@@ -181,6 +503,7 @@ where
     M: mixer::Mixer<P::Notifier>,
 {
     inner: P::Mixed,
+    context: &'static event::TraceContext,
 }
 
 impl<P, M> MixedSubscriber<P, M>
@@ -188,8 +511,8 @@ where
     P: mixer::SubscriberMixer<M>,
     M: mixer::Mixer<P::Notifier>,
 {
-    pub(crate) fn new(inner: &'static P) -> Self {
-        Self { inner: <P as mixer::SubscriberMixer<M>>::__new_mixed(inner) }
+    pub(crate) fn new(inner: &'static P, context: &'static event::TraceContext) -> Self {
+        Self { inner: <P as mixer::SubscriberMixer<M>>::__new_mixed(inner), context }
     }
 
     pub async fn next(&mut self) -> event::Event<P::Notifier, M> {
@@ -199,6 +522,25 @@ where
     pub fn try_next(&mut self) -> Option<event::Event<P::Notifier, M>> {
         <Self as traits::SubscribedMixed<M>>::try_next(self)
     }
+
+    pub fn poll_next(&mut self, cx: &mut Context<'_>) -> Poll<event::Event<P::Notifier, M>> {
+        self.context.leave();
+        let event = core::task::ready!(P::__poll_mixed(&mut self.inner, cx));
+        self.context.enter(event.meta.trace);
+        Poll::Ready(event)
+    }
+}
+
+impl<P, M> Stream for MixedSubscriber<P, M>
+where
+    P: mixer::SubscriberMixer<M, Mixed: Unpin>,
+    M: mixer::Mixer<P::Notifier>,
+{
+    type Item = event::Event<P::Notifier, M>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        MixedSubscriber::poll_next(self.get_mut(), cx).map(Some)
+    }
 }
 
 impl<P, M> traits::SubscribedMixed<M> for MixedSubscriber<P, M>
@@ -210,10 +552,16 @@ where
     type Notifier = P::Notifier;
 
     async fn next(&mut self) -> event::Event<Self::Notifier, M> {
-        P::__mixed(&mut self.inner).await
+        self.context.leave();
+        let event = P::__mixed(&mut self.inner).await;
+        self.context.enter(event.meta.trace);
+        event
     }
 
     fn try_next(&mut self) -> Option<event::Event<Self::Notifier, M>> {
-        P::__try_mixed(&mut self.inner)
+        self.context.leave();
+        let event = P::__try_mixed(&mut self.inner)?;
+        self.context.enter(event.meta.trace);
+        Some(event)
     }
 }
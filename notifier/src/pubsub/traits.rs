@@ -13,8 +13,17 @@ pub trait PubSub: Sized + 'static {
 
     const NEW: Self;
 }
+#[diagnostic::on_unimplemented(
+    message = "`{E}` is not published by the service it belongs to",
+    label = "no publisher for `{E}`",
+    note = "add `#[notifier_publisher(event = ...)]` to the service set in `#[notifier_event(service = ...)]`"
+)]
 pub trait Publisher<E>: PubSub {
     const PROTECTED: bool = false;
+
+    fn __retained(&self) -> Option<&super::Retained<Self, E>> {
+        None
+    }
 }
 
 pub type GetSubscriberRet<N, E> = super::GetDynSubscription<N, E>;
@@ -120,6 +129,12 @@ pub trait CanPublish<E: __evt::Event<Self::Notifier>>: CanPublishRaw<E> {
                 id,
                 err
             ),
+            super::Error::Cancelled(meta, id) => log::warn!(
+                target: &crate::log_target(meta.name()),
+                "Service {} has cancelled request {}, its response is dropped",
+                meta.name(),
+                id
+            ),
         }
     }
 }
@@ -0,0 +1,311 @@
+//! Recording of the notifier traffic and its replay.
+//!
+//! An installed [`Hook`] sees every published event and the outcome of every delivery. [`Recorder`] keeps
+//! the last of them in a static ring buffer, [`replay`] publishes a recording again on the host
+
+use crate::event::{self, traits as __evt};
+use crate::pubsub::{self, traits as __pub};
+use crate::service::traits as __svc;
+use crate::traits as __traits;
+use core::cell::{Cell, RefCell};
+use core::marker::PhantomData;
+use core::sync::atomic::{AtomicBool, Ordering};
+use embassy_sync::blocking_mutex::{raw, Mutex};
+use embassy_time::{Instant, Timer};
+
+type RawMutex = raw::CriticalSectionRawMutex;
+
+static HOOK: Mutex<RawMutex, Cell<Option<&'static dyn Hook>>> = Mutex::new(Cell::new(None));
+/// Set while a hook is installed, the publish path doesn't enter the critical section otherwise
+static INSTALLED: AtomicBool = AtomicBool::new(false);
+
+/// Encoding of an event in a recording, `TAG` identifies the event type and must be unique in the notifier
+pub trait Encode<N: __traits::Notifier>: __evt::Event<N> {
+    const TAG: u16;
+
+    /// Writes the event into `buf`, returns the used length or `None` if it doesn't fit
+    fn encode(&self, buf: &mut [u8]) -> Option<usize>;
+    fn decode(buf: &[u8]) -> Option<Self>;
+}
+
+trait MaybeEncode<N: __traits::Notifier> {
+    const TAG: Option<u16>;
+    fn encode(&self, buf: &mut [u8]) -> Option<usize>;
+}
+impl<N: __traits::Notifier, E: __evt::Event<N>> MaybeEncode<N> for E {
+    default const TAG: Option<u16> = None;
+    default fn encode(&self, _: &mut [u8]) -> Option<usize> {
+        None
+    }
+}
+impl<N: __traits::Notifier, E: Encode<N>> MaybeEncode<N> for E {
+    const TAG: Option<u16> = Some(<E as Encode<N>>::TAG);
+    fn encode(&self, buf: &mut [u8]) -> Option<usize> {
+        <E as Encode<N>>::encode(self, buf)
+    }
+}
+
+/// Service instance, as `crate::Metadata` identifies it
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Endpoint {
+    pub id: usize,
+    pub index: Option<usize>,
+}
+
+impl From<&crate::Metadata> for Endpoint {
+    fn from(meta: &crate::Metadata) -> Self {
+        Self { id: meta.id, index: meta.index }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Outcome {
+    Delivered,
+    Inactive,
+    Failed,
+}
+
+/// Event being published
+pub struct Publish<'a> {
+    pub tag: Option<u16>,
+    pub src: Endpoint,
+    pub id: usize,
+    pub trace: event::Trace,
+    pub timestamp: Instant,
+    encode: &'a dyn Fn(&mut [u8]) -> Option<usize>,
+}
+
+impl Publish<'_> {
+    /// Encodes the event with its [`Encode`] implementation, `None` if it has none or `buf` is too small
+    pub fn encode(&self, buf: &mut [u8]) -> Option<usize> {
+        (self.encode)(buf)
+    }
+}
+
+/// Outcome of sending an event to one subscriber
+#[derive(Debug, Clone, Copy)]
+pub struct Delivery {
+    pub src: Endpoint,
+    pub id: usize,
+    pub dst: Endpoint,
+    pub outcome: Outcome,
+}
+
+/// RPC request given up by its caller, nothing is published for it
+#[derive(Debug, Clone, Copy)]
+pub struct Cancel {
+    pub src: Endpoint,
+    pub id: usize,
+    pub dst: Endpoint,
+}
+
+/// Observer of the publish path, called from the publishing context
+pub trait Hook: Sync {
+    fn publish(&self, event: &Publish);
+    fn delivery(&self, delivery: Delivery);
+    /// Called from the context of the caller
+    fn cancel(&self, _cancel: Cancel) {}
+}
+
+pub fn install(hook: &'static dyn Hook) {
+    HOOK.lock(|cell| {
+        cell.set(Some(hook));
+        INSTALLED.store(true, Ordering::Release);
+    })
+}
+
+pub fn uninstall() {
+    HOOK.lock(|cell| {
+        INSTALLED.store(false, Ordering::Release);
+        cell.set(None);
+    })
+}
+
+fn hook() -> Option<&'static dyn Hook> {
+    if !INSTALLED.load(Ordering::Acquire) {
+        return None;
+    }
+    HOOK.lock(Cell::get)
+}
+
+pub(crate) fn publish<N, E>(event: &event::Event<N, E>)
+where
+    N: __traits::Notifier,
+    E: __evt::Event<N>,
+{
+    let Some(hook) = hook() else {
+        return;
+    };
+    hook.publish(&Publish {
+        tag: <E as MaybeEncode<N>>::TAG,
+        src: event.meta.src.into(),
+        id: event.meta.id,
+        trace: event.meta.trace,
+        timestamp: event.meta.timestamp,
+        encode: &|buf| MaybeEncode::<N>::encode(&event.data, buf),
+    })
+}
+
+pub(crate) fn delivery(meta: &event::Metadata, dst: &crate::Metadata, outcome: Outcome) {
+    if let Some(hook) = hook() {
+        hook.delivery(Delivery { src: meta.src.into(), id: meta.id, dst: dst.into(), outcome })
+    }
+}
+
+pub(crate) fn cancel(src: &crate::Metadata, id: usize, dst: &crate::Metadata) {
+    if let Some(hook) = hook() {
+        hook.cancel(Cancel { src: src.into(), id, dst: dst.into() })
+    }
+}
+
+/// Recorded entry, `S` is the maximal size of an encoded event
+#[derive(Debug, Clone)]
+pub enum Record<const S: usize> {
+    Publish {
+        tag: Option<u16>,
+        src: Endpoint,
+        id: usize,
+        trace: event::Trace,
+        timestamp: Instant,
+        /// `None` if the event has no [`Encode`] implementation or doesn't fit
+        data: Option<heapless::Vec<u8, S>>,
+    },
+    Delivery(Delivery),
+    Cancel(Cancel),
+}
+
+/// Ring buffer of the last `C` records, the oldest are overwritten
+pub struct Recorder<const C: usize, const S: usize> {
+    records: Mutex<RawMutex, RefCell<heapless::HistoryBuffer<Record<S>, C>>>,
+}
+
+impl<const C: usize, const S: usize> Recorder<C, S> {
+    pub const fn new() -> Self {
+        Self { records: Mutex::new(RefCell::new(heapless::HistoryBuffer::new())) }
+    }
+
+    pub fn install(&'static self) {
+        install(self)
+    }
+
+    /// Visits the records from the oldest one
+    pub fn records(&self, mut visitor: impl FnMut(&Record<S>)) {
+        self.records.lock(|records| records.borrow().oldest_ordered().for_each(&mut visitor))
+    }
+
+    pub fn len(&self) -> usize {
+        self.records.lock(|records| records.borrow().len())
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn clear(&self) {
+        self.records.lock(|records| records.borrow_mut().clear())
+    }
+}
+
+impl<const C: usize, const S: usize> Default for Recorder<C, S> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<const C: usize, const S: usize> Hook for Recorder<C, S> {
+    fn publish(&self, event: &Publish) {
+        let mut buf = [0; S];
+        let data = event
+            .tag
+            .and_then(|_| event.encode(&mut buf))
+            .and_then(|len| heapless::Vec::from_slice(buf.get(..len)?).ok());
+        let record = Record::Publish {
+            tag: event.tag,
+            src: event.src,
+            id: event.id,
+            trace: event.trace,
+            timestamp: event.timestamp,
+            data,
+        };
+        self.records.lock(|records| records.borrow_mut().write(record))
+    }
+
+    fn delivery(&self, delivery: Delivery) {
+        self.records.lock(|records| records.borrow_mut().write(Record::Delivery(delivery)))
+    }
+
+    fn cancel(&self, cancel: Cancel) {
+        self.records.lock(|records| records.borrow_mut().write(Record::Cancel(cancel)))
+    }
+}
+
+/// Publisher of one event type for [`replay`]
+pub struct Handler<N> {
+    tag: u16,
+    publish: fn(&[u8], usize) -> bool,
+    _phantom: PhantomData<fn() -> N>,
+}
+
+impl<N: __traits::Notifier> Handler<N> {
+    pub const fn new<E>() -> Self
+    where
+        E: Encode<N>,
+        N: __traits::NotifierService<E::Service>,
+        [(); <E::Service as __svc::Service<N>>::COUNT]:,
+        pubsub::PubSub<crate::GetPubSub<N, E::Service>>: __pub::CanPublish<E, Notifier = N>,
+    {
+        Self { tag: E::TAG, publish: Self::publish::<E>, _phantom: PhantomData }
+    }
+
+    fn publish<E>(data: &[u8], index: usize) -> bool
+    where
+        E: Encode<N>,
+        N: __traits::NotifierService<E::Service>,
+        [(); <E::Service as __svc::Service<N>>::COUNT]:,
+        pubsub::PubSub<crate::GetPubSub<N, E::Service>>: __pub::CanPublish<E, Notifier = N>,
+    {
+        let container = <E::Service as __svc::Service<N>>::notif();
+        let Some(event) = E::decode(data).filter(|_| index < container.inner.len()) else {
+            return false;
+        };
+        __pub::CanPublish::publish(__pub::GetPubSub::__get(container, index), event);
+        true
+    }
+}
+
+/// Publishes the recorded events that started a trace again, in their order and with their gaps,
+/// from the instances that published them. Events published in reaction are left to the services,
+/// these are the ones published while an event was handled, see [`event::TraceContext`].
+/// Returns the number of the published events
+pub async fn replay<'r, N, const S: usize>(
+    records: impl IntoIterator<Item = &'r Record<S>>,
+    handlers: &[Handler<N>],
+) -> usize
+where
+    N: __traits::Notifier,
+{
+    let mut replayed = 0;
+    let mut last = None;
+    for record in records {
+        let Record::Publish { tag: Some(tag), src, trace, timestamp, data: Some(data), .. } = record else {
+            continue;
+        };
+        if trace.parent().is_some() {
+            continue;
+        }
+        let Some(handler) = handlers.iter().find(|handler| handler.tag == *tag) else {
+            log::warn!("Replay: no handler for event tag {}", tag);
+            continue;
+        };
+
+        if let Some(last) = last.replace(*timestamp) {
+            Timer::after(timestamp.saturating_duration_since(last)).await;
+        }
+        if (handler.publish)(data, src.index.unwrap_or_default()) {
+            replayed += 1;
+        } else {
+            log::warn!("Replay: event {} with tag {} is not published", trace, tag);
+        }
+    }
+    replayed
+}
@@ -2,15 +2,24 @@ use crate::event::{self, traits as __evt};
 use crate::pubsub::{self, traits as __pubsub};
 use crate::service::traits as __svc;
 use crate::traits::*;
+use core::cell::RefCell;
+use core::future::{poll_fn, Future};
 use core::ops::{Deref, Index};
-use embassy_time::{Duration, Timer};
+use core::pin::Pin;
+use core::task::{Context, Poll};
+use embassy_futures::join::join_array;
+use embassy_sync::blocking_mutex::{raw, Mutex};
+use embassy_sync::waitqueue::MultiWakerRegistration;
+use embassy_time::{Duration, Instant, Timer};
 use futures_util::future::pending;
-use futures_util::FutureExt;
+use futures_util::{FutureExt, Stream};
 use varuemb_utils::assert::*;
 use varuemb_utils::select;
 
 pub mod traits;
 
+type RawMutex = raw::CriticalSectionRawMutex;
+
 pub type Result<T, R: traits::Rpc> =
     core::result::Result<T, pubsub::Error<R::Notifier, Request<R>, GetResponseError<R, R::Service>>>;
 pub type GetResponse<P, R> = Response<pubsub::GetPubSub<P, R>>;
@@ -24,6 +33,8 @@ where
     R::Service: traits::RpcProvider<R::Notifier>,
 {
     inner: [traits::GetRpc<R>; C],
+    /// Caller of all instances at once
+    all: Rpc<R>,
 }
 
 impl<R: traits::Rpc, const C: usize> Container<R, C>
@@ -33,8 +44,48 @@ where
     pub(crate) fn new(
         channel: __pubsub::GetSubscriberRet<R::Notifier, Response<R>>,
         meta: &'static crate::Metadata,
+        context: &'static event::TraceContext,
     ) -> Self {
-        Self { inner: core::array::from_fn(|index| R::__new_rpc(Rpc { index, src: meta, channel })) }
+        Self {
+            inner: core::array::from_fn(|index| R::__new_rpc(Rpc { index, src: meta, channel, context })),
+            all: Rpc { index: 0, src: meta, channel, context },
+        }
+    }
+}
+
+impl<Req, Resp, N, R, S, const C: usize> Container<R, C>
+where
+    N: Notifier,
+    Req: __evt::Event<N>,
+    Resp: __evt::Event<N>,
+    R: traits::Rpc<Notifier = N, Service = S> + __pubsub::CanMetadata,
+    S: traits::RpcProvider<N, Request = Req, Response = Resp, Impl = R> + 'static,
+{
+    /// Sends the request to every instance at once and gathers their responses within `timeout`
+    pub async fn call_all(&self, req: Req, timeout: Option<Duration>) -> [Result<Resp, R>; C]
+    where
+        [(); S::COUNT]:,
+        N: NotifierService<S>,
+        pubsub::PubSub<R>: __pubsub::CanPublish<Request<R>, Notifier = N>,
+    {
+        self.all.process_all::<C>(req, timeout, false).await
+    }
+
+    /// Sends the request to every instance at once, returns the first success and cancels the others.
+    /// Fails with the error of the lowest instance if none succeeds
+    pub async fn call_any(&self, req: Req, timeout: Option<Duration>) -> Result<Resp, R>
+    where
+        [(); S::COUNT]:,
+        N: NotifierService<S>,
+        pubsub::PubSub<R>: __pubsub::CanPublish<Request<R>, Notifier = N>,
+        Assert<{ C > 0 }>: IsTrue,
+    {
+        let results = self.all.process_all::<C>(req, timeout, true).await;
+        // `C > 0`, so the fallback is never taken
+        results
+            .into_iter()
+            .reduce(|found, res| if found.is_err() && res.is_ok() { res } else { found })
+            .unwrap_or_else(|| Err(pubsub::Error::Cancelled(self.all.src, 0)))
     }
 }
 
@@ -90,6 +141,108 @@ where
     }
 }
 
+struct CancelState<const C: usize> {
+    notices: heapless::HistoryBuffer<(&'static crate::Metadata, usize), C>,
+    wakers: MultiWakerRegistration<C>,
+    count: usize,
+}
+
+/// Cancel notices of the requests to a provider instance. `C` is the number of requests the instance may
+/// hold, the queued ones and the one handled, so only notices of requests already answered are forgotten.
+///
+/// The notices are a side channel next to pub/sub: the caller writes them from a destructor, which can't
+/// await a publish nor fail, and they must not wait behind the queued requests they cancel. They therefore
+/// carry no trace and aren't logged as events, only the recorder sees them
+pub struct Cancellation<const C: usize> {
+    inner: Mutex<RawMutex, RefCell<CancelState<C>>>,
+}
+
+impl<const C: usize> Cancellation<C> {
+    pub const fn default() -> Self {
+        let state = CancelState { notices: heapless::HistoryBuffer::new(), wakers: MultiWakerRegistration::new(), count: 0 };
+        Self { inner: Mutex::new(RefCell::new(state)) }
+    }
+
+    fn contains(state: &CancelState<C>, src: &'static crate::Metadata, id: usize) -> bool {
+        state.notices.as_slice().iter().any(|&(caller, cancelled)| cancelled == id && *caller == *src)
+    }
+}
+
+impl<const C: usize> traits::CancelNotices for Cancellation<C> {
+    fn cancel(&self, src: &'static crate::Metadata, id: usize) {
+        self.inner.lock(|state| {
+            let mut state = state.borrow_mut();
+            state.notices.write((src, id));
+            state.count += 1;
+            state.wakers.wake();
+        })
+    }
+
+    fn is_cancelled(&self, src: &'static crate::Metadata, id: usize) -> bool {
+        self.inner.lock(|state| Self::contains(&state.borrow(), src, id))
+    }
+
+    fn poll_cancelled(&self, src: &'static crate::Metadata, id: usize, cx: &mut Context<'_>) -> Poll<()> {
+        self.inner.lock(|state| {
+            let mut state = state.borrow_mut();
+            if Self::contains(&state, src, id) {
+                return Poll::Ready(());
+            }
+            state.wakers.register(cx.waker());
+            Poll::Pending
+        })
+    }
+
+    fn count(&self) -> usize {
+        self.inner.lock(|state| state.borrow().count)
+    }
+}
+
+/// Call of one instance by `process_all`
+enum Call<T> {
+    Waiting(pubsub::PublishData, CancelGuard),
+    Done(T),
+}
+
+/// Cancels the request when dropped, unless its response has arrived
+struct CancelGuard {
+    cancellation: Option<&'static dyn traits::CancelNotices>,
+    src: &'static crate::Metadata,
+    dst: &'static crate::Metadata,
+    id: Option<usize>,
+}
+
+impl CancelGuard {
+    fn new<P>(pubsub: &'static pubsub::PubSub<P>, src: &'static crate::Metadata, id: usize) -> Self
+    where
+        P: __pubsub::PubSub,
+        P::Notifier: NotifierService<P::Service>,
+    {
+        let cancellation = traits::Cancellable::__cancellation(&pubsub.inner);
+        Self { cancellation, src, dst: pubsub.metadata(), id: Some(id) }
+    }
+
+    fn disarm(&mut self) {
+        self.id = None
+    }
+
+    fn cancel(&mut self) {
+        let Some(id) = self.id.take() else {
+            return;
+        };
+        if let Some(cancellation) = self.cancellation {
+            cancellation.cancel(self.src, id)
+        }
+        crate::recorder::cancel(self.src, id, self.dst);
+    }
+}
+
+impl Drop for CancelGuard {
+    fn drop(&mut self) {
+        self.cancel()
+    }
+}
+
 pub struct Request<R: traits::Rpc>
 where
     R::Service: traits::RpcProvider<R::Notifier>,
@@ -165,6 +318,8 @@ where
     id: usize,
     req_discriminant: usize,
     src: &'static crate::Metadata,
+    timestamp: Instant,
+    trace: event::Trace,
     pubsub: &'static pubsub::PubSub<R>,
     data: Option<<R::Service as traits::RpcProvider<R::Notifier>>::Request>,
 }
@@ -187,6 +342,39 @@ where
         self.src
     }
 
+    /// When the request was published
+    pub fn timestamp(&self) -> Instant {
+        self.timestamp
+    }
+
+    /// Trace of the request, responses continue it
+    pub fn trace(&self) -> event::Trace {
+        self.trace
+    }
+
+    /// Whether the caller has timed out or dropped the call
+    pub fn is_cancelled(&self) -> bool {
+        let cancellation = traits::Cancellable::__cancellation(&self.pubsub.inner);
+        cancellation.is_some_and(|cancellation| cancellation.is_cancelled(self.src, self.id))
+    }
+
+    /// Resolves once the caller has timed out or dropped the call, to abort the handler early
+    pub async fn cancelled(&self) {
+        let Some(cancellation) = traits::Cancellable::__cancellation(&self.pubsub.inner) else {
+            return pending().await;
+        };
+        poll_fn(|cx| cancellation.poll_cancelled(self.src, self.id, cx)).await
+    }
+
+    /// Runs the handler until it's done, `None` if the caller cancels the request first and the handler is
+    /// dropped
+    pub async fn handle<F: Future>(&self, handler: F) -> Option<F::Output> {
+        select! {
+            out = handler => { Some(out) }
+            _cancelled = self.cancelled() => { None }
+        }
+    }
+
     pub async fn response(&self, resp: Resp) -> Result<(), R>
     where
         R::Notifier: NotifierService<R::Service>,
@@ -215,6 +403,11 @@ where
         if self.req_discriminant != resp_discriminant {
             return Err(pubsub::Error::IncorrectResponse(self.src, self.id));
         }
+        // Nobody reads it
+        if self.is_cancelled() {
+            return Err(pubsub::Error::Cancelled(self.src, self.id));
+        }
+        let _scope = self.pubsub.context.scope(self.trace);
         self.pubsub
             .publisher()
             .set_targets([self.src])
@@ -230,6 +423,10 @@ where
         R::Notifier: NotifierService<R::Service>,
         pubsub::PubSub<R>: __pubsub::CanPublish<Response<R>, Notifier = R::Notifier>,
     {
+        if self.is_cancelled() {
+            return Err(pubsub::Error::Cancelled(self.src, self.id));
+        }
+        let _scope = self.pubsub.context.scope(self.trace);
         self.pubsub
             .publisher()
             .set_targets([self.src])
@@ -238,6 +435,154 @@ where
             .publish(Response { id: self.id, data: Err(err) });
         Ok(())
     }
+
+    /// Sender of a streaming response, `wrap` is the response variant of the handler
+    pub fn stream<T>(&self, wrap: fn(Option<T>) -> Resp) -> ResponseSender<'_, R, T> {
+        ResponseSender { request: self, wrap }
+    }
+}
+
+/// Sender of the items of a streaming response, the stream ends with [`ResponseSender::end`] or an error
+pub struct ResponseSender<'r, R: traits::Rpc, T>
+where
+    R::Service: traits::RpcProvider<R::Notifier>,
+{
+    request: &'r RpcRequest<R>,
+    wrap: fn(Option<T>) -> GetResponseData<R, R::Service>,
+}
+
+impl<Req, Resp, RespE, R: traits::Rpc, T> ResponseSender<'_, R, T>
+where
+    Req: __evt::Event<R::Notifier>,
+    Resp: __evt::Event<R::Notifier>,
+    RespE: core::fmt::Debug + Clone + 'static,
+    R::Service: traits::RpcProvider<R::Notifier, Request = Req, Response = Resp, Error = RespE>,
+    R::Notifier: NotifierService<R::Service>,
+    pubsub::PubSub<R>: __pubsub::CanPublish<Response<R>, Notifier = R::Notifier>,
+    for<'r> &'r Resp: Into<usize>,
+{
+    /// Whether the caller has stopped reading the stream
+    pub fn is_cancelled(&self) -> bool {
+        self.request.is_cancelled()
+    }
+
+    pub async fn send(&mut self, item: T) -> Result<(), R> {
+        self.request.raw_response((self.wrap)(Some(item)), None).await
+    }
+
+    pub async fn send_with(&mut self, item: T, timeout: crate::Duration) -> Result<(), R> {
+        self.request.raw_response((self.wrap)(Some(item)), Some(timeout)).await
+    }
+
+    /// Terminates the stream
+    pub async fn end(self) -> Result<(), R> {
+        self.request.raw_response((self.wrap)(None), None).await
+    }
+
+    /// Terminates the stream with an error
+    pub fn error(self, err: RespE) -> Result<(), R> {
+        self.request.response_err(err)
+    }
+}
+
+/// Timeout of a streaming call
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum StreamTimeout {
+    /// Each item has to arrive within the duration after the previous one
+    PerItem(Duration),
+    /// The whole stream has to end within the duration
+    Overall(Duration),
+}
+
+impl StreamTimeout {
+    fn duration(self) -> Duration {
+        match self {
+            StreamTimeout::PerItem(duration) | StreamTimeout::Overall(duration) => duration,
+        }
+    }
+}
+
+/// Items of a streaming call, ends after the terminator or the first error
+pub struct ResponseStream<R: traits::Rpc, T>
+where
+    R::Service: traits::RpcProvider<R::Notifier>,
+{
+    subscriber: pubsub::Subscriber<R::Notifier, Response<R>>,
+    id: usize,
+    trace: event::Trace,
+    src: &'static crate::Metadata,
+    dst: &'static crate::Metadata,
+    mapper: fn(GetResponseData<R, R::Service>) -> Option<Option<T>>,
+    timeout: Option<StreamTimeout>,
+    timer: Option<Timer>,
+    done: bool,
+    cancel: CancelGuard,
+}
+
+impl<R: traits::Rpc, T> ResponseStream<R, T>
+where
+    R::Service: traits::RpcProvider<R::Notifier>,
+{
+    /// Item of the response, `None` for the terminator
+    fn item(&self, data: GetResponseRes<R, R::Service>) -> Option<Result<T, R>> {
+        match data {
+            Ok(resp) => match (self.mapper)(resp) {
+                Some(item) => item.map(Ok),
+                None => Some(Err(pubsub::Error::IncorrectResponse(self.src, self.id))),
+            },
+            Err(err) => Some(Err(pubsub::Error::Response(self.src, self.id, err))),
+        }
+    }
+}
+
+impl<R: traits::Rpc, T> Stream for ResponseStream<R, T>
+where
+    R::Service: traits::RpcProvider<R::Notifier>,
+{
+    type Item = Result<T, R>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = self.get_mut();
+        if this.done {
+            return Poll::Ready(None);
+        }
+        while let Poll::Ready(event) = this.subscriber.poll_next(cx) {
+            if event.meta.src != this.dst {
+                continue;
+            }
+            let resp = event.data();
+            if resp.id != this.id {
+                continue;
+            }
+            if let Some(StreamTimeout::PerItem(duration)) = this.timeout {
+                this.timer = Some(Timer::after(duration));
+            }
+            let item = this.item(resp.data);
+            this.done = !matches!(item, Some(Ok(_)));
+            if this.done {
+                this.cancel.disarm();
+            }
+            return Poll::Ready(item);
+        }
+
+        let (Some(timeout), Some(timer)) = (this.timeout, this.timer.as_mut()) else {
+            return Poll::Pending;
+        };
+        if Pin::new(timer).poll(cx).is_pending() {
+            return Poll::Pending;
+        }
+        this.done = true;
+        this.cancel.cancel();
+        let meta = event::Metadata {
+            id: this.id,
+            src: this.src,
+            dst: this.dst,
+            dropped: false,
+            timestamp: Instant::now(),
+            trace: this.trace,
+        };
+        Poll::Ready(Some(Err(pubsub::Error::Timeout(meta, timeout.duration()))))
+    }
 }
 
 pub struct Rpc<R: traits::Rpc>
@@ -247,6 +592,7 @@ where
     index: usize,
     src: &'static crate::Metadata,
     channel: __pubsub::GetSubscriberRet<R::Notifier, Response<R>>,
+    context: &'static event::TraceContext,
 }
 
 impl<Req, Resp, N, R, S> Rpc<R>
@@ -267,7 +613,9 @@ where
     }
 
     fn subscriber(&self) -> pubsub::Subscriber<R::Notifier, Response<R>> {
-        pubsub::Subscriber::new(self.channel)
+        let mut subscriber = pubsub::Subscriber::new(self.channel);
+        subscriber.context = Some(self.context);
+        subscriber
     }
 
     pub fn process_send_only(&self, req: Req) -> Result<(), R>
@@ -281,6 +629,7 @@ where
         let mut err = None;
         publisher
             .publisher()
+            .set_context(self.context)
             .break_after_error(true)
             .set_targets([publisher.metadata()])
             .set_error_handler::<_, GetResponseError<R, S>>(|e| err = Some(e))
@@ -293,23 +642,18 @@ where
         Ok(())
     }
 
-    pub async fn process<D>(
-        &self,
-        req: Req,
-        timeout: Option<Duration>,
-        cb: core::result::Result<impl Fn(Resp) -> Option<D>, D>,
-    ) -> Result<D, R>
+    async fn send_request(&self, index: usize, req: Req, timeout: Option<Duration>) -> Result<pubsub::PublishData, R>
     where
         [(); S::COUNT]:,
         N: NotifierService<S>,
         pubsub::PubSub<R>: __pubsub::CanPublish<Request<R>, Notifier = N>,
     {
-        let publisher = Self::publisher(self.index);
-        let mut subscriber = self.subscriber();
+        let publisher = Self::publisher(index);
 
         let mut err = None;
         let res = publisher
             .publisher()
+            .set_context(self.context)
             .break_after_error(true)
             .set_targets([publisher.metadata()])
             .set_error_handler::<_, GetResponseError<R, S>>(|e| err = Some(e))
@@ -320,11 +664,70 @@ where
             pubsub::PubSub::<R>::print_error(&err);
             return Err(err);
         }
+        Ok(res)
+    }
+
+    /// Sends the request, attempting it again after the errors `retry` accepts. The request that reached the
+    /// provider is never sent again
+    async fn send_retried(
+        &self,
+        req: Req,
+        timeout: Option<Duration>,
+        retry: Option<pubsub::Retry>,
+    ) -> Result<pubsub::PublishData, R>
+    where
+        [(); S::COUNT]:,
+        N: NotifierService<S>,
+        pubsub::PubSub<R>: __pubsub::CanPublish<Request<R>, Notifier = N>,
+    {
+        let mut attempt = 1;
+        loop {
+            let err = match self.send_request(self.index, req.clone(), timeout).await {
+                Ok(res) => return Ok(res),
+                Err(err) => err,
+            };
+            let Some((retry, delay)) = retry.and_then(|retry| Some((retry, retry.next(attempt, &err)?))) else {
+                return Err(err);
+            };
+            log::error!(
+                target: &crate::log_target(self.src.name()),
+                "Service {} request to {} failed, cause {}, retry {}/{} in {}",
+                self.src.name(),
+                Self::publisher(self.index).metadata(),
+                pubsub::retry::kind(&err),
+                attempt + 1,
+                retry.attempts(),
+                delay
+            );
+            Timer::after(delay).await;
+            attempt += 1;
+        }
+    }
+
+    /// Calls the provider, `cb` maps the response or gives the value to return without waiting for one.
+    /// Sending the request is attempted again after the errors `retry` accepts, waiting for the response is not
+    pub async fn process<D>(
+        &self,
+        req: Req,
+        timeout: Option<Duration>,
+        retry: Option<pubsub::Retry>,
+        cb: core::result::Result<impl Fn(Resp) -> Option<D>, D>,
+    ) -> Result<D, R>
+    where
+        [(); S::COUNT]:,
+        N: NotifierService<S>,
+        pubsub::PubSub<R>: __pubsub::CanPublish<Request<R>, Notifier = N>,
+    {
+        let publisher = Self::publisher(self.index);
+        let mut subscriber = self.subscriber();
+        let res = self.send_retried(req, timeout, retry).await?;
 
         let cb = match cb {
             Ok(cb) => cb,
             Err(ret) => return Ok(ret),
         };
+        // Cancels the request on timeout or if this future is dropped
+        let mut cancel = CancelGuard::new(publisher, self.src, res.id);
 
         let meta = publisher.metadata();
         let response = async move {
@@ -354,22 +757,139 @@ where
             }
         };
         select! {
-            response = response => { response }
-            duration = timeout => { Err(pubsub::Error::Timeout(event::Metadata { id: res.id, src: self.src, dst: meta }, duration)) }
+            response = response => {
+                cancel.disarm();
+                response
+            }
+            duration = timeout => {
+                let meta = event::Metadata {
+                    id: res.id,
+                    src: self.src,
+                    dst: meta,
+                    dropped: false,
+                    timestamp: Instant::now(),
+                    trace: res.trace,
+                };
+                Err(pubsub::Error::Timeout(meta, duration))
+            }
         }
     }
 
+    /// Sends the request to the instances `0..C` at once and gathers the responses, all within `timeout`.
+    /// With `first` it stops at the first success, the instances not answered by then are cancelled
+    async fn process_all<const C: usize>(&self, req: Req, timeout: Option<Duration>, first: bool) -> [Result<Resp, R>; C]
+    where
+        [(); S::COUNT]:,
+        N: NotifierService<S>,
+        pubsub::PubSub<R>: __pubsub::CanPublish<Request<R>, Notifier = N>,
+    {
+        let mut subscriber = self.subscriber();
+        // One deadline for the sends and the responses
+        let deadline = timeout.map(|timeout| Instant::now() + timeout);
+        let sends =
+            core::array::from_fn(|index| self.send_request(index, req.clone(), timeout).map(move |res| (index, res)));
+        let mut calls = join_array(sends).await.map(|(index, sent)| match sent {
+            Ok(res) => {
+                let cancel = CancelGuard::new(Self::publisher(index), self.src, res.id);
+                Call::Waiting(res, cancel)
+            }
+            Err(err) => Call::Done(Err(err)),
+        });
+
+        let responses = async {
+            while calls.iter().any(|call| matches!(call, Call::Waiting(..))) {
+                let event = subscriber.next().await;
+                let src = event.meta.src;
+                let Some(index) = calls.iter().enumerate().position(|(index, call)| {
+                    matches!(call, Call::Waiting(res, _) if res.id == event.data.id)
+                        && Self::publisher(index).metadata() == src
+                }) else {
+                    continue;
+                };
+                let Call::Waiting(res, cancel) = &mut calls[index] else {
+                    continue;
+                };
+                cancel.disarm();
+                let id = res.id;
+                let resp = event.data();
+                let ok = resp.data.is_ok();
+                calls[index] = Call::Done(resp.data.map_err(|err| pubsub::Error::Response(self.src, id, err)));
+                if ok && first {
+                    break;
+                }
+            }
+        };
+        let timeout = async {
+            match (timeout, deadline) {
+                (Some(duration), Some(deadline)) => Timer::at(deadline).map(|_| duration).await,
+                _ => pending().await,
+            }
+        };
+        let timed_out = select! {
+            _responses = responses => { None }
+            duration = timeout => { Some(duration) }
+        };
+
+        // The guards of the calls still waiting cancel them
+        calls.map(|call| match (call, timed_out) {
+            (Call::Done(res), _) => res,
+            (Call::Waiting(res, cancel), Some(duration)) => {
+                let meta = event::Metadata {
+                    id: res.id,
+                    src: self.src,
+                    dst: cancel.dst,
+                    dropped: false,
+                    timestamp: Instant::now(),
+                    trace: res.trace,
+                };
+                Err(pubsub::Error::Timeout(meta, duration))
+            }
+            // Another instance has answered first
+            (Call::Waiting(res, _cancel), None) => Err(pubsub::Error::Cancelled(self.src, res.id)),
+        })
+    }
+
+    /// Sends a streaming request, `mapper` gives the item of the response variant or `None` for the terminator.
+    /// Sending the request is attempted again after the errors `retry` accepts
+    pub async fn process_stream<T>(
+        &self,
+        req: Req,
+        timeout: Option<StreamTimeout>,
+        retry: Option<pubsub::Retry>,
+        mapper: fn(Resp) -> Option<Option<T>>,
+    ) -> Result<ResponseStream<R, T>, R>
+    where
+        [(); S::COUNT]:,
+        N: NotifierService<S>,
+        pubsub::PubSub<R>: __pubsub::CanPublish<Request<R>, Notifier = N>,
+    {
+        let subscriber = self.subscriber();
+        let res = self.send_retried(req, timeout.map(StreamTimeout::duration), retry).await?;
+        Ok(ResponseStream {
+            subscriber,
+            id: res.id,
+            trace: res.trace,
+            src: self.src,
+            dst: Self::publisher(self.index).metadata(),
+            mapper,
+            timeout,
+            timer: timeout.map(|timeout| Timer::after(timeout.duration())),
+            done: false,
+            cancel: CancelGuard::new(Self::publisher(self.index), self.src, res.id),
+        })
+    }
+
     pub(crate) async fn request<'a>(subscriber: &mut pubsub::Subscriber<N, Request<R>>) -> RpcRequest<R>
     where
         [(); S::COUNT]:,
         N: NotifierService<S>,
         for<'r> &'r Req: Into<usize>,
     {
-        let event::Event { data: Request { src, data }, meta: event::Metadata { id, dst, .. }, .. } =
+        let event::Event { data: Request { src, data }, meta: event::Metadata { id, dst, timestamp, trace, .. }, .. } =
             subscriber.next().await;
 
         let pubsub = Self::publisher(dst.index.unwrap_or_default());
 
-        RpcRequest { id, src, pubsub, req_discriminant: (&data).into(), data: Some(data) }
+        RpcRequest { id, src, timestamp, trace, pubsub, req_discriminant: (&data).into(), data: Some(data) }
     }
 }
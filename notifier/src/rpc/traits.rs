@@ -2,6 +2,7 @@ use crate::event::traits as __evt;
 use crate::pubsub::traits as __pubsub;
 use crate::service::traits as __svc;
 use crate::traits::*;
+use core::task::{Context, Poll};
 
 pub trait Rpc: __pubsub::PubSub
 where
@@ -21,6 +22,26 @@ where
     const PROTECTED: bool = true;
 }
 
+/// Cancel notices of the requests to a provider instance, see [`super::Cancellation`]
+pub trait CancelNotices: Sync {
+    fn cancel(&self, src: &'static crate::Metadata, id: usize);
+    fn is_cancelled(&self, src: &'static crate::Metadata, id: usize) -> bool;
+    fn poll_cancelled(&self, src: &'static crate::Metadata, id: usize, cx: &mut Context<'_>) -> Poll<()>;
+    /// Number of the requests cancelled so far
+    fn count(&self) -> usize;
+}
+
+/// Implemented by `#[derive(Service)]` for the services with `rpc`
+pub trait Cancellable {
+    fn __cancellation(&'static self) -> Option<&'static dyn CancelNotices>;
+}
+
+impl<P: __pubsub::PubSub> Cancellable for P {
+    default fn __cancellation(&'static self) -> Option<&'static dyn CancelNotices> {
+        None
+    }
+}
+
 pub type GetSubscriberRet<N, S> = __pubsub::GetSubscriberRet<N, super::Response<<S as __svc::Service<N>>::Impl>>;
 pub type GetRpc<R: Rpc> = <R::Service as RpcProvider<R::Notifier>>::Rpc;
 
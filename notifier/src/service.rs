@@ -49,12 +49,13 @@ where
         &self,
         channel: __pub::GetSubscriberRet<N, rpc::Response<S::Impl>>,
         meta: &'static crate::Metadata,
+        context: &'static crate::event::TraceContext,
     ) -> rpc::Container<S::Impl, { S::COUNT }>
     where
         S::Impl: __rpc::Rpc,
         S: __rpc::RpcProvider<N>,
     {
-        rpc::Container::new(channel, meta)
+        rpc::Container::new(channel, meta, context)
     }
 }
 
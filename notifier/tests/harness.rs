@@ -0,0 +1,914 @@
+//! Services driven through the test harness. Every test has its own notifier, the notifiers are statics
+//! shared by the tests running in parallel
+
+#![allow(incomplete_features)]
+#![feature(const_trait_impl)]
+#![feature(generic_const_exprs)]
+#![feature(specialization)]
+
+use core::future::{pending, Future};
+use embassy_futures::block_on;
+use embassy_futures::join::join;
+use embassy_futures::select::{select, Either};
+use embassy_time::Timer;
+use varuemb::notifier::harness::Harness;
+use varuemb::notifier::service::traits::Service as _;
+use varuemb::notifier::{notifier, pubsub, rpc_handlers, Duration, Event, Service};
+
+const TIMEOUT: Duration = Duration::from_millis(500);
+
+/// Runs the service under test until the test is done
+async fn run<T>(service: impl Future, test: impl Future<Output = T>) -> T {
+    match select(service, test).await {
+        Either::First(_) => unreachable!("Services run forever"),
+        Either::Second(out) => out,
+    }
+}
+
+mod retained {
+    use super::*;
+
+    #[notifier]
+    pub struct Notif {
+        sensor: Sensor,
+        display: Display,
+    }
+
+    #[derive(Service)]
+    #[notifier_service(notifier = Notif)]
+    #[notifier_publisher(event = Level, retain)]
+    #[notifier_subscriber(event = Shown, count = 1)]
+    pub struct Sensor;
+
+    #[derive(Service)]
+    #[notifier_service(notifier = Notif)]
+    #[notifier_publisher(event = Shown)]
+    #[notifier_subscriber(event = Level, mode = latest)]
+    pub struct Display;
+
+    #[derive(Event, Clone, Debug)]
+    #[notifier_event(notifier = Notif, service = Sensor)]
+    pub struct Level(u8);
+
+    #[derive(Event, Clone, Debug)]
+    #[notifier_event(notifier = Notif, service = Display)]
+    pub struct Shown(u8);
+
+    async fn display() {
+        let mut levels = Display::notif().subscriber::<Level>();
+        loop {
+            let Level(level) = levels.next_raw().await;
+            Display::notif().publish(Shown(level));
+        }
+    }
+
+    #[test]
+    fn late_subscriber_gets_retained_event() {
+        let harness = Harness::<Notif, Display>::new();
+        harness.publish(Level(3));
+
+        block_on(run(display(), async {
+            assert_eq!(harness.expect_published::<Shown>(TIMEOUT).await.data().0, 3);
+            harness.expect_not_published::<Shown>(Duration::from_millis(50)).await;
+        }));
+    }
+}
+
+mod latest {
+    use super::*;
+    use varuemb::notifier::introspect::Topology;
+    use varuemb::notifier::traits::Notifier as _;
+
+    #[notifier]
+    pub struct Notif {
+        sensor: Sensor,
+        display: Display,
+    }
+
+    #[derive(Service)]
+    #[notifier_service(notifier = Notif)]
+    #[notifier_publisher(event = Level)]
+    pub struct Sensor;
+
+    #[derive(Service)]
+    #[notifier_service(notifier = Notif)]
+    #[notifier_subscriber(event = Level, mode = latest)]
+    pub struct Display;
+
+    #[derive(Event, Clone, Debug)]
+    #[notifier_event(notifier = Notif, service = Sensor)]
+    pub struct Level(u8);
+
+    #[test]
+    fn overwritten_event_is_marked_dropped() {
+        let harness = Harness::<Notif, Sensor>::new();
+        let mut levels = Display::notif().subscriber::<Level>();
+
+        Sensor::notif().publish(Level(1));
+        Sensor::notif().publish(Level(2));
+        let level = levels.try_next().expect("The latest level is kept");
+        assert!(level.dropped());
+        assert_eq!(level.data().0, 2);
+        assert!(levels.try_next().is_none());
+
+        let display = harness.metadata::<Display>(0);
+        let mut subscriptions = Vec::new();
+        Notif::get().subscriptions(|meta, subscription| {
+            if *meta == *display {
+                subscriptions.push(subscription)
+            }
+        });
+        assert_eq!(subscriptions.len(), 1);
+        assert_eq!(subscriptions[0].overflow, pubsub::Overflow::Latest);
+        assert_eq!(subscriptions[0].dropped, 1);
+    }
+}
+
+mod introspect {
+    use super::*;
+    use varuemb::notifier::introspect::{Subscription, Topology};
+    use varuemb::notifier::traits::Notifier as _;
+
+    #[notifier]
+    pub struct Notif {
+        sensor: Sensor,
+        display: Display,
+    }
+
+    #[derive(Service)]
+    #[notifier_service(notifier = Notif)]
+    #[notifier_publisher(event = Level)]
+    pub struct Sensor;
+
+    #[derive(Service)]
+    #[notifier_service(notifier = Notif)]
+    #[notifier_subscriber(event = Level, count = 4, overflow = drop_oldest)]
+    pub struct Display;
+
+    #[derive(Event, Clone, Debug)]
+    #[notifier_event(notifier = Notif, service = Sensor)]
+    pub struct Level(u8);
+
+    /// Subscription of the display as introspection reports it
+    fn display(harness: &Harness<Notif, Sensor>) -> Subscription {
+        let display = harness.metadata::<Display>(0);
+        let mut found = None;
+        Notif::get().subscriptions(|meta, subscription| {
+            if *meta == *display {
+                found = Some(subscription)
+            }
+        });
+        found.expect("The display subscribes to the level")
+    }
+
+    #[test]
+    fn reports_queue_depths() {
+        let harness = Harness::<Notif, Sensor>::new();
+        let mut levels = Display::notif().subscriber::<Level>();
+
+        for level in 0..3 {
+            Sensor::notif().publish(Level(level));
+        }
+        let subscription = display(&harness);
+        assert_eq!((subscription.len, subscription.capacity, subscription.receivers), (3, 4, 1));
+
+        assert_eq!(levels.try_next_raw().map(|level| level.0), Some(0));
+        assert_eq!(display(&harness).len, 2);
+
+        for level in 3..6 {
+            Sensor::notif().publish(Level(level));
+        }
+        let subscription = display(&harness);
+        assert_eq!((subscription.len, subscription.dropped), (4, 1));
+        assert_eq!(subscription.overflow, pubsub::Overflow::DropOldest);
+    }
+}
+
+mod resubscribe {
+    use super::*;
+
+    #[notifier]
+    pub struct Notif {
+        sensor: Sensor,
+        display: Display,
+    }
+
+    #[derive(Service)]
+    #[notifier_service(notifier = Notif)]
+    #[notifier_publisher(event = Level, retain)]
+    pub struct Sensor;
+
+    #[derive(Service)]
+    #[notifier_service(notifier = Notif)]
+    #[notifier_subscriber(event = Level, count = 2)]
+    pub struct Display;
+
+    #[derive(Event, Clone, Debug)]
+    #[notifier_event(notifier = Notif, service = Sensor)]
+    pub struct Level(u8);
+
+    #[test]
+    fn first_receiver_gets_retained_event_again() {
+        let _harness = Harness::<Notif, Sensor>::new();
+
+        let mut levels = Display::notif().subscriber::<Level>();
+        Sensor::notif().publish(Level(3));
+        assert_eq!(levels.try_next_raw().map(|level| level.0), Some(3));
+        drop(levels);
+
+        let mut levels = Display::notif().subscriber::<Level>();
+        assert_eq!(levels.try_next_raw().map(|level| level.0), Some(3));
+        assert!(levels.try_next_raw().is_none());
+    }
+}
+
+mod overflow {
+    use super::*;
+
+    #[notifier]
+    pub struct Notif {
+        sensor: Sensor,
+        display: Display,
+    }
+
+    #[derive(Service)]
+    #[notifier_service(notifier = Notif)]
+    #[notifier_publisher(event = Tick)]
+    #[notifier_publisher(event = Alarm)]
+    pub struct Sensor;
+
+    #[derive(Service)]
+    #[notifier_service(notifier = Notif)]
+    #[notifier_subscriber(event = Tick, count = 2, overflow = drop_oldest)]
+    #[notifier_subscriber(event = Alarm, count = 1, overflow = error)]
+    pub struct Display;
+
+    #[derive(Event, Clone, Debug)]
+    #[notifier_event(notifier = Notif, service = Sensor)]
+    pub struct Tick(u8);
+
+    #[derive(Event, Clone, Debug)]
+    #[notifier_event(notifier = Notif, service = Sensor)]
+    pub struct Alarm;
+
+    #[test]
+    fn drop_oldest_keeps_newest_events() {
+        let harness = Harness::<Notif, Display>::new();
+        let mut ticks = Display::notif().subscriber::<Tick>();
+
+        for tick in 1..=3 {
+            assert_eq!(harness.publish(Tick(tick)).errors, 0);
+        }
+        assert_eq!(ticks.dropped(), 1);
+        assert_eq!(ticks.try_next_raw().map(|tick| tick.0), Some(2));
+        assert_eq!(ticks.try_next_raw().map(|tick| tick.0), Some(3));
+        assert!(ticks.try_next_raw().is_none());
+    }
+
+    #[test]
+    fn error_reports_full_subscription() {
+        let harness = Harness::<Notif, Display>::new();
+        let mut alarms = Display::notif().subscriber::<Alarm>();
+
+        assert_eq!(harness.publish(Alarm).published, 1);
+        let full = harness.publish(Alarm);
+        assert_eq!((full.published, full.errors), (0, 1));
+        assert!(alarms.try_next_raw().is_some());
+        assert!(alarms.try_next_raw().is_none());
+    }
+}
+
+mod filters {
+    use super::*;
+
+    #[notifier]
+    pub struct Notif {
+        sensor: Sensor,
+        display: Display,
+    }
+
+    #[derive(Service)]
+    #[notifier_service(notifier = Notif)]
+    #[notifier_publisher(event = Tick)]
+    pub struct Sensor;
+
+    #[derive(Service)]
+    #[notifier_service(notifier = Notif)]
+    #[notifier_subscriber(event = Tick, count = 4)]
+    pub struct Display;
+
+    #[derive(Event, Clone, Debug)]
+    #[notifier_event(notifier = Notif, service = Sensor)]
+    pub struct Tick(u8);
+
+    #[test]
+    fn filter_skips_rejected_events() {
+        let harness = Harness::<Notif, Display>::new();
+        let Ok(mut ticks) = Display::notif().subscriber::<Tick>().with_filter(|tick, _| tick.0 % 2 == 0) else {
+            panic!("The only subscriber sets the filter")
+        };
+        // The queue is shared, another subscriber can't filter it again
+        assert!(Display::notif().subscriber::<Tick>().with_filter(|_, _| true).is_err());
+
+        for tick in 1..=4 {
+            let data = harness.publish(Tick(tick));
+            assert_eq!((data.published, data.not_published), (usize::from(tick % 2 == 0), usize::from(tick % 2 == 1)));
+        }
+        assert_eq!(ticks.try_next_raw().map(|tick| tick.0), Some(2));
+        assert_eq!(ticks.try_next_raw().map(|tick| tick.0), Some(4));
+        assert!(ticks.try_next_raw().is_none());
+    }
+}
+
+mod streaming {
+    use super::*;
+    use futures_util::StreamExt;
+
+    #[notifier]
+    pub struct Notif {
+        sensor: Sensor,
+        display: Display,
+    }
+
+    #[derive(Service)]
+    #[notifier_service(notifier = Notif, rpc = 1)]
+    #[notifier_subscriber(event = Average, count = 1)]
+    pub struct Sensor;
+
+    #[rpc_handlers(notifier = Notif, request = SensorRequest, response = SensorResponse)]
+    impl Sensor {
+        #[stream]
+        #[rpc_handler(duration = 500)]
+        fn history(count: u8) -> u8;
+    }
+
+    #[derive(Service)]
+    #[notifier_service(notifier = Notif)]
+    #[notifier_publisher(event = Average)]
+    #[notifier_rpc_subscriber(service = Sensor, count = 4)]
+    pub struct Display;
+
+    #[derive(Event, Clone, Debug)]
+    #[notifier_event(notifier = Notif, service = Display)]
+    pub struct Average(u8);
+
+    async fn display() {
+        let sensor = Display::notif().rpc::<Sensor>();
+        let history = sensor.history(3).await.expect("Sensor takes the request");
+        let levels = history.map(|level| level.expect("Sensor streams the levels")).collect::<Vec<_>>().await;
+        let average = levels.iter().sum::<u8>() / levels.len() as u8;
+        Display::notif().publish(Average(average));
+        pending().await
+    }
+
+    #[test]
+    fn stream_ends_with_terminator() {
+        let harness = Harness::<Notif, Display>::new();
+
+        block_on(run(display(), async {
+            let request = harness.request::<Sensor>(0).await;
+            assert!(matches!(request.peak(), SensorRequest::History { count: 3 }));
+            let mut sender = request.stream(SensorResponse::History);
+            for level in [1, 2, 6] {
+                sender.send(level).await.expect("Display reads the stream");
+            }
+            sender.end().await.expect("Display reads the stream");
+
+            assert_eq!(harness.expect_published::<Average>(TIMEOUT).await.data().0, 3);
+        }));
+    }
+}
+
+mod cancellation {
+    use super::*;
+
+    #[notifier]
+    pub struct Notif {
+        sensor: Sensor,
+        display: Display,
+    }
+
+    #[derive(Service)]
+    #[notifier_service(notifier = Notif, rpc = 1)]
+    #[notifier_subscriber(event = Unavailable, count = 1)]
+    pub struct Sensor;
+
+    #[rpc_handlers(notifier = Notif, request = SensorRequest, response = SensorResponse)]
+    impl Sensor {
+        #[rpc_handler(duration = 50)]
+        fn level() -> u8;
+    }
+
+    #[derive(Service)]
+    #[notifier_service(notifier = Notif)]
+    #[notifier_publisher(event = Unavailable)]
+    #[notifier_rpc_subscriber(service = Sensor, count = 1)]
+    pub struct Display;
+
+    #[derive(Event, Clone, Debug)]
+    #[notifier_event(notifier = Notif, service = Display)]
+    pub struct Unavailable;
+
+    async fn display() {
+        let sensor = Display::notif().rpc::<Sensor>();
+        if let Err(pubsub::Error::Timeout(..)) = sensor.level().await {
+            Display::notif().publish(Unavailable);
+        }
+        pending().await
+    }
+
+    #[test]
+    fn timed_out_call_is_cancelled() {
+        let harness = Harness::<Notif, Display>::new();
+
+        block_on(run(display(), async {
+            let request = harness.request::<Sensor>(0).await;
+            assert!(matches!(select(request.cancelled(), Timer::after(TIMEOUT)).await, Either::First(())));
+            assert!(request.is_cancelled());
+            harness.expect_published::<Unavailable>(TIMEOUT).await;
+            // Nobody waits for the late response anymore
+            let res = request.response(SensorResponse::Level(1)).await;
+            assert!(matches!(res, Err(pubsub::Error::Cancelled(_, _))));
+        }));
+    }
+}
+
+mod serve {
+    use super::*;
+
+    #[notifier]
+    pub struct Notif {
+        sensor: Sensor,
+        display: Display,
+    }
+
+    #[derive(Service)]
+    #[notifier_service(notifier = Notif, rpc = 1)]
+    pub struct Sensor;
+
+    #[rpc_handlers(notifier = Notif, request = SensorRequest, response = SensorResponse)]
+    impl Sensor {
+        #[rpc_handler(duration = 50)]
+        fn level() -> u8;
+    }
+
+    #[derive(Service)]
+    #[notifier_service(notifier = Notif)]
+    #[notifier_publisher(event = Read)]
+    #[notifier_rpc_subscriber(service = Sensor, count = 1)]
+    pub struct Display;
+
+    #[derive(Event, Clone, Debug)]
+    #[notifier_event(notifier = Notif, service = Display)]
+    pub struct Read(Option<u8>);
+
+    /// Hangs on the first call, answers the next ones
+    struct Levels {
+        calls: u8,
+    }
+
+    impl SensorHandler for Levels {
+        async fn level(&mut self) -> Result<u8, ()> {
+            self.calls += 1;
+            if self.calls == 1 {
+                pending::<()>().await
+            }
+            Ok(7)
+        }
+    }
+
+    async fn sensor() {
+        let mut requests = Sensor::notif().subscriber();
+        Sensor::serve_rpc(&mut requests, &mut Levels { calls: 0 }).await
+    }
+
+    async fn display() {
+        let sensor = Display::notif().rpc::<Sensor>();
+        for _ in 0..2 {
+            Display::notif().publish(Read(sensor.level().await.ok()));
+        }
+        pending().await
+    }
+
+    #[test]
+    fn cancelled_handler_is_dropped() {
+        let harness = Harness::<Notif, Display>::new();
+
+        block_on(run(join(sensor(), display()), async {
+            assert_eq!(harness.expect_published::<Read>(TIMEOUT).await.data().0, None);
+            assert_eq!(harness.expect_published::<Read>(TIMEOUT).await.data().0, Some(7));
+        }));
+    }
+}
+
+mod trace {
+    use super::*;
+
+    #[notifier]
+    pub struct Notif {
+        sensor: Sensor,
+        display: Display,
+    }
+
+    #[derive(Service)]
+    #[notifier_service(notifier = Notif, rpc = 1)]
+    #[notifier_publisher(event = Tick)]
+    #[notifier_subscriber(event = Shown, count = 1)]
+    pub struct Sensor;
+
+    #[rpc_handlers(notifier = Notif, request = SensorRequest, response = SensorResponse)]
+    impl Sensor {
+        fn level() -> u8;
+    }
+
+    #[derive(Service)]
+    #[notifier_service(notifier = Notif)]
+    #[notifier_publisher(event = Shown)]
+    #[notifier_subscriber(event = Tick, count = 1)]
+    #[notifier_rpc_subscriber(service = Sensor, count = 1)]
+    pub struct Display;
+
+    #[derive(Event, Clone, Debug)]
+    #[notifier_event(notifier = Notif, service = Sensor)]
+    pub struct Tick;
+
+    #[derive(Event, Clone, Debug)]
+    #[notifier_event(notifier = Notif, service = Display)]
+    pub struct Shown(u8);
+
+    async fn display() {
+        let mut ticks = Display::notif().subscriber::<Tick>();
+        let sensor = Display::notif().rpc::<Sensor>();
+        loop {
+            ticks.next().await;
+            if let Ok(level) = sensor.level().await {
+                Display::notif().publish(Shown(level));
+            }
+        }
+    }
+
+    #[test]
+    fn rpc_round_trip_continues_the_trace() {
+        let harness = Harness::<Notif, Display>::new();
+
+        block_on(run(display(), async {
+            Timer::after(Duration::from_millis(5)).await;
+            let tick = harness.publish(Tick).trace;
+
+            let request = harness.request::<Sensor>(0).await;
+            assert_eq!((request.trace().id(), request.trace().parent()), (tick.id(), Some(tick.span())));
+            request.response(SensorResponse::Level(4)).await.unwrap();
+
+            let shown = harness.expect_published::<Shown>(TIMEOUT).await;
+            assert_eq!((shown.trace().id(), shown.trace().parent()), (tick.id(), Some(tick.span())));
+            assert_eq!(shown.data().0, 4);
+        }));
+    }
+}
+
+mod call_all {
+    use super::*;
+
+    #[notifier]
+    pub struct Notif {
+        sensor: Sensor,
+        display: Display,
+    }
+
+    #[derive(Service)]
+    #[notifier_service(notifier = Notif, count = 2, rpc = 1)]
+    #[notifier_subscriber(event = Total, count = 1)]
+    pub struct Sensor;
+
+    #[rpc_handlers(notifier = Notif, request = SensorRequest, response = SensorResponse)]
+    impl Sensor {
+        fn level() -> u8;
+    }
+
+    #[derive(Service)]
+    #[notifier_service(notifier = Notif)]
+    #[notifier_publisher(event = Total)]
+    #[notifier_rpc_subscriber(service = Sensor, count = 2)]
+    pub struct Display;
+
+    #[derive(Event, Clone, Debug)]
+    #[notifier_event(notifier = Notif, service = Display)]
+    pub struct Total(u8);
+
+    async fn display() {
+        let sensor = Display::notif().rpc::<Sensor>();
+        let levels = sensor.call_all(SensorRequest::Level {}, Some(TIMEOUT)).await;
+        let total = levels
+            .into_iter()
+            .map(|level| match level {
+                Ok(SensorResponse::Level(level)) => level,
+                Err(_) => 0,
+            })
+            .sum();
+        Display::notif().publish(Total(total));
+        pending().await
+    }
+
+    #[test]
+    fn every_instance_answers() {
+        let harness = Harness::<Notif, Display>::new();
+
+        block_on(run(display(), async {
+            join(
+                harness.answer::<Sensor>(0, |_| Ok(SensorResponse::Level(2))),
+                harness.answer::<Sensor>(1, |_| Ok(SensorResponse::Level(5))),
+            )
+            .await;
+            assert_eq!(harness.expect_published::<Total>(TIMEOUT).await.data().0, 7);
+        }));
+    }
+}
+
+mod retry {
+    use super::*;
+
+    #[notifier]
+    pub struct Notif {
+        sensor: Sensor,
+        display: Display,
+    }
+
+    #[derive(Service)]
+    #[notifier_service(notifier = Notif)]
+    #[notifier_publisher(event = Level)]
+    pub struct Sensor;
+
+    #[derive(Service)]
+    #[notifier_service(notifier = Notif)]
+    #[notifier_subscriber(event = Level, count = 1, overflow = error)]
+    pub struct Display;
+
+    #[derive(Event, Clone, Debug)]
+    #[notifier_event(notifier = Notif, service = Sensor)]
+    pub struct Level(u8);
+
+    async fn sensor() -> pubsub::PublishData {
+        Sensor::notif().publish(Level(1));
+        Sensor::notif()
+            .publisher::<Level>()
+            .set_retry(pubsub::Retry::fixed(3, Duration::from_millis(20)))
+            .publish_with(Level(2), Some(TIMEOUT))
+            .await
+    }
+
+    #[test]
+    fn full_subscription_is_retried() {
+        let harness = Harness::<Notif, Sensor>::new();
+        let mut levels = Display::notif().subscriber::<Level>();
+
+        block_on(async {
+            let (data, ()) = join(sensor(), async {
+                Timer::after(Duration::from_millis(5)).await;
+                assert_eq!(levels.next_raw().await.0, 1);
+            })
+            .await;
+            assert_eq!((data.published, data.errors), (1, 0));
+        });
+        assert_eq!(levels.try_next_raw().map(|level| level.0), Some(2));
+        assert_eq!(harness.take_all::<Level>().len(), 2);
+    }
+}
+
+mod sync_retry {
+    use super::*;
+
+    #[notifier]
+    pub struct Notif {
+        sensor: Sensor,
+        display: Display,
+    }
+
+    #[derive(Service)]
+    #[notifier_service(notifier = Notif)]
+    #[notifier_publisher(event = Level)]
+    pub struct Sensor;
+
+    #[derive(Service)]
+    #[notifier_service(notifier = Notif)]
+    #[notifier_subscriber(event = Level, count = 1, overflow = error)]
+    pub struct Display;
+
+    #[derive(Event, Clone, Debug)]
+    #[notifier_event(notifier = Notif, service = Sensor)]
+    pub struct Level(u8);
+
+    #[test]
+    fn sync_publish_reports_full_at_once() {
+        let harness = Harness::<Notif, Sensor>::new();
+        let mut levels = Display::notif().subscriber::<Level>();
+
+        Sensor::notif().publish(Level(1));
+        let data = Sensor::notif()
+            .publisher::<Level>()
+            .set_retry(pubsub::Retry::fixed(3, Duration::from_millis(20)))
+            .publish(Level(2));
+        assert_eq!((data.published, data.errors), (0, 1));
+        assert_eq!(levels.try_next_raw().map(|level| level.0), Some(1));
+        assert!(levels.try_next_raw().is_none());
+        assert_eq!(harness.take_all::<Level>().len(), 2);
+    }
+}
+
+mod stream {
+    use super::*;
+    use core::pin::Pin;
+    use core::task::{Context, Poll};
+    use futures_util::stream::{self, Stream, StreamExt};
+    use varuemb::notifier::event;
+    use varuemb::notifier::pubsub::EventStreamExt;
+
+    #[notifier]
+    pub struct Notif {
+        sensor: Sensor,
+        display: Display,
+    }
+
+    #[derive(Service)]
+    #[notifier_service(notifier = Notif, count = 2)]
+    #[notifier_publisher(event = Level)]
+    pub struct Sensor;
+
+    #[derive(Service)]
+    #[notifier_service(notifier = Notif)]
+    #[notifier_subscriber(event = Level, count = 1, overflow = drop_oldest)]
+    pub struct Display;
+
+    #[derive(Event, Clone, Debug)]
+    #[notifier_event(notifier = Notif, service = Sensor)]
+    pub struct Level(u8);
+
+    /// Events of the levels published by the sensor instances
+    fn levels(harness: &Harness<Notif, Sensor>, levels: &[(usize, u8)]) -> Vec<event::Event<Notif, Level>> {
+        for &(index, level) in levels {
+            harness.publish_as(harness.metadata::<Sensor>(index), Level(level));
+        }
+        harness.take_all::<Level>()
+    }
+
+    /// Yields the items, then ends once and panics if polled again
+    struct Ends<I> {
+        items: I,
+        ended: bool,
+    }
+
+    impl<I: Iterator + Unpin> Stream for Ends<I> {
+        type Item = I::Item;
+
+        fn poll_next(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+            let this = self.get_mut();
+            assert!(!this.ended, "Polled after the end");
+            let item = this.items.next();
+            this.ended = item.is_none();
+            Poll::Ready(item)
+        }
+    }
+
+    fn ends<T>(items: Vec<T>) -> Ends<std::vec::IntoIter<T>> {
+        Ends { items: items.into_iter(), ended: false }
+    }
+
+    /// Level and dropped flag of the event
+    fn level(event: Option<event::Event<Notif, Level>>) -> Option<(u8, bool)> {
+        event.map(|event| {
+            let dropped = event.dropped();
+            (event.data().0, dropped)
+        })
+    }
+
+    #[test]
+    fn filter_src_keeps_the_instance() {
+        let harness = Harness::<Notif, Sensor>::new();
+        let events = levels(&harness, &[(0, 1), (1, 2), (0, 3)]);
+
+        block_on(async {
+            let mut second = stream::iter(events.clone()).filter_src(harness.metadata::<Sensor>(1));
+            assert_eq!(level(second.next().await), Some((2, false)));
+            assert_eq!(level(second.next().await), None);
+
+            let mut all = stream::iter(events).filter_src(Sensor::notif().metadata_service());
+            assert_eq!(level(all.next().await), Some((1, false)));
+            assert_eq!(level(all.next().await), Some((2, false)));
+            assert_eq!(level(all.next().await), Some((3, false)));
+        });
+    }
+
+    #[test]
+    fn debounce_yields_the_latest_after_quiet() {
+        let harness = Harness::<Notif, Sensor>::new();
+        let events = levels(&harness, &[(0, 1), (0, 2)]);
+
+        block_on(async {
+            let mut debounced = stream::iter(events).chain(stream::pending()).debounce(Duration::from_millis(20));
+            assert_eq!(level(debounced.next().await), Some((2, true)));
+            let quiet = select(debounced.next(), Timer::after(Duration::from_millis(50))).await;
+            assert!(matches!(quiet, Either::Second(())));
+        });
+    }
+
+    #[test]
+    fn debounce_flushes_at_the_end() {
+        let harness = Harness::<Notif, Sensor>::new();
+        let events = levels(&harness, &[(0, 1), (0, 2), (0, 3)]);
+
+        block_on(async {
+            let mut debounced = ends(events).debounce(Duration::from_secs(60));
+            assert_eq!(level(debounced.next().await), Some((3, true)));
+            assert_eq!(level(debounced.next().await), None);
+            assert_eq!(level(debounced.next().await), None);
+        });
+    }
+
+    #[test]
+    fn throttle_marks_the_event_after_skipped_ones() {
+        let harness = Harness::<Notif, Sensor>::new();
+        let [first, second, late]: [_; 3] = levels(&harness, &[(0, 1), (0, 2), (0, 3)]).try_into().unwrap();
+
+        block_on(async {
+            let late = Box::pin(stream::once(async move {
+                Timer::after(Duration::from_millis(40)).await;
+                late
+            }));
+            let mut throttled = stream::iter([first, second]).chain(late).throttle(Duration::from_millis(20));
+            assert_eq!(level(throttled.next().await), Some((1, false)));
+            assert_eq!(level(throttled.next().await), Some((3, true)));
+            assert_eq!(level(throttled.next().await), None);
+        });
+    }
+
+    #[test]
+    fn coalesce_yields_the_latest_at_the_end() {
+        let harness = Harness::<Notif, Sensor>::new();
+        let events = levels(&harness, &[(0, 1), (0, 2)]);
+
+        block_on(async {
+            let mut coalesced = ends(events).coalesce();
+            assert_eq!(level(coalesced.next().await), Some((2, true)));
+            assert_eq!(level(coalesced.next().await), None);
+            assert_eq!(level(coalesced.next().await), None);
+        });
+    }
+}
+
+mod replay {
+    use super::*;
+    use varuemb::notifier::recorder::{self, Encode, Handler, Recorder};
+
+    #[notifier]
+    pub struct Notif {
+        sensor: Sensor,
+        display: Display,
+    }
+
+    #[derive(Service)]
+    #[notifier_service(notifier = Notif)]
+    #[notifier_publisher(event = Level)]
+    pub struct Sensor;
+
+    #[derive(Service)]
+    #[notifier_service(notifier = Notif)]
+    #[notifier_subscriber(event = Level, count = 4, overflow = drop_oldest)]
+    pub struct Display;
+
+    #[derive(Event, Clone, Debug)]
+    #[notifier_event(notifier = Notif, service = Sensor)]
+    pub struct Level(u8);
+
+    impl Encode<Notif> for Level {
+        const TAG: u16 = 1;
+
+        fn encode(&self, buf: &mut [u8]) -> Option<usize> {
+            *buf.first_mut()? = self.0;
+            Some(1)
+        }
+
+        fn decode(buf: &[u8]) -> Option<Self> {
+            buf.first().map(|&level| Level(level))
+        }
+    }
+
+    // The hook is global, the records of the other tests have no tag and aren't replayed
+    static RECORDER: Recorder<256, 4> = Recorder::new();
+
+    #[test]
+    fn recording_is_replayed() {
+        let harness = Harness::<Notif, Sensor>::new();
+
+        RECORDER.install();
+        harness.publish(Level(1));
+        harness.publish(Level(2));
+        recorder::uninstall();
+        let mut records = Vec::new();
+        RECORDER.records(|record| records.push(record.clone()));
+        assert_eq!(harness.take_all::<Level>().len(), 2);
+
+        let handlers = [Handler::new::<Level>()];
+        assert_eq!(block_on(recorder::replay(&records, &handlers)), 2);
+        let replayed = harness.take_all::<Level>().into_iter().map(|level| level.data().0).collect::<Vec<_>>();
+        assert_eq!(replayed, [1, 2]);
+    }
+}